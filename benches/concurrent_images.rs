@@ -0,0 +1,100 @@
+// Load generator for the static host. Start the server first, then:
+// cargo bench --bench concurrent_images -- [path] [concurrency] [requests]
+// The default path is a full-size photo, bigger than CACHE_MAX_FILE so every request
+// reads it from disk; put a 4 MiB jpeg there first. Expect it to print requests per
+// second and p50/p99 latency with 0 errors. Anything other than a 200 counts as an
+// error and the first one is printed, so a wrong path doesn't time the 404 page.
+use {
+    std::{
+        env,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    },
+    tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+        task::JoinSet,
+    },
+};
+
+const ADDR: &str = "127.0.0.1:7878";
+const HOST: &str = "localhost:7878";
+
+async fn fetch(path: &str) -> std::io::Result<usize> {
+    let mut stream = TcpStream::connect(ADDR).await?;
+    stream
+        .write_all(format!("GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, HOST).as_bytes())
+        .await?;
+    let mut response = vec![];
+    stream.read_to_end(&mut response).await?;
+    match response.starts_with(b"HTTP/1.1 200") {
+        true => Ok(response.len()),
+        false => Err(std::io::Error::other(String::from_utf8_lossy(
+            &response[..response.len().min(40)],
+        ))),
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().skip(1).filter(|a| a != "--bench").collect();
+    let path = args
+        .first()
+        .cloned()
+        .unwrap_or("/pub/bench/photo.jpg".to_string());
+    let concurrency: usize = args.get(1).and_then(|v| v.parse().ok()).unwrap_or(64);
+    let requests: usize = args.get(2).and_then(|v| v.parse().ok()).unwrap_or(10_000);
+
+    let issued = Arc::new(AtomicUsize::new(0));
+    let start = Instant::now();
+    let mut workers = JoinSet::new();
+    for _ in 0..concurrency {
+        let (path, issued) = (path.clone(), issued.clone());
+        workers.spawn(async move {
+            let (mut latencies, mut bytes, mut errors) = (vec![], 0, vec![]);
+            while issued.fetch_add(1, Ordering::Relaxed) < requests {
+                let sent = Instant::now();
+                match fetch(&path).await {
+                    Ok(n) => {
+                        bytes += n;
+                        latencies.push(sent.elapsed());
+                    }
+                    Err(e) => errors.push(e),
+                }
+            }
+            (latencies, bytes, errors)
+        });
+    }
+
+    let (mut latencies, mut bytes, mut errors) = (Vec::<Duration>::new(), 0, vec![]);
+    while let Some(Ok((l, b, e))) = workers.join_next().await {
+        latencies.extend(l);
+        bytes += b;
+        errors.extend(e);
+    }
+    if let Some(e) = errors.first() {
+        eprintln!("first error: {}", e);
+    }
+    let elapsed = start.elapsed();
+    latencies.sort();
+
+    let percentile = |p: usize| {
+        latencies
+            .get((latencies.len() * p / 100).min(latencies.len().saturating_sub(1)))
+            .copied()
+            .unwrap_or_default()
+    };
+    println!(
+        "{path} - {concurrency} concurrent\n\
+        {} ok - {} errors - {bytes}b in {elapsed:.2?}\n\
+        {:.0} req/s - p50 {:.2?} - p99 {:.2?}",
+        latencies.len(),
+        errors.len(),
+        latencies.len() as f64 / elapsed.as_secs_f64(),
+        percentile(50),
+        percentile(99),
+    );
+}
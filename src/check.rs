@@ -0,0 +1,282 @@
+use {
+    crate::{
+        consts::IMAGE_DIR,
+        mycology::{
+            generate::Rank,
+            images,
+            parse::{self, ShroomInfo},
+        },
+    },
+    std::{
+        cmp::Ordering,
+        collections::{HashMap, HashSet},
+        fmt,
+        path::PathBuf,
+        process::ExitCode,
+    },
+    tokio::fs,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Severity {
+    Warning,
+    Error,
+}
+
+struct Problem {
+    severity: Severity,
+    at: String,
+    message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}: {}", severity, self.at, self.message)
+    }
+}
+
+fn error(at: &str, message: String) -> Problem {
+    Problem {
+        severity: Severity::Error,
+        at: at.to_string(),
+        message,
+    }
+}
+
+fn warning(at: &str, message: String) -> Problem {
+    Problem {
+        severity: Severity::Warning,
+        at: at.to_string(),
+        message,
+    }
+}
+
+// 0 when clean, 1 on errors (or warnings when strict), 2 when the data doesn't load at all
+pub async fn run(strict: bool) -> ExitCode {
+    let (_, shroompedia) = match parse::yaml().await {
+        Ok(v) => v,
+        Err(e) => {
+            println!("error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let (images, sidecars) = images::build(&shroompedia).await;
+
+    let mut problems = vec![];
+    problems.extend(sidecars.into_iter().map(|(url, e)| error(&url, e)));
+    problems.extend(duplicates(&shroompedia));
+    problems.extend(common_names(&shroompedia));
+    for shroom in &shroompedia {
+        problems.extend(names(shroom));
+        if shroom.info.blurb.trim().is_empty() {
+            problems.push(warning(&shroom.url, "missing or empty blurb".to_string()));
+        }
+        if images.get(&shroom.url).is_none_or(Vec::is_empty) {
+            problems.push(warning(
+                &shroom.url,
+                format!("no images in {}", image_dir(&shroom.url).display()),
+            ));
+        }
+    }
+    problems.extend(orphans(&shroompedia).await);
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    let errors = problems
+        .iter()
+        .filter(|p| p.severity == Severity::Error)
+        .count();
+    println!(
+        "{} species checked - {} errors - {} warnings",
+        shroompedia.len(),
+        errors,
+        problems.len() - errors
+    );
+
+    match (errors, problems.len()) {
+        (0, 0) => ExitCode::SUCCESS,
+        (0, _) if !strict => ExitCode::SUCCESS,
+        _ => ExitCode::from(1),
+    }
+}
+
+fn duplicates(shroompedia: &[ShroomInfo]) -> Vec<Problem> {
+    let mut problems = vec![];
+    let mut latin_names: HashMap<String, &str> = HashMap::new();
+    let mut urls: HashMap<String, &str> = HashMap::new();
+    for shroom in shroompedia {
+        if let Some(other) = latin_names.insert(shroom.latin_name.to_lowercase(), &shroom.url) {
+            problems.push(error(
+                &shroom.url,
+                format!(
+                    "latin name `{}` is also used by {}",
+                    shroom.latin_name, other
+                ),
+            ));
+        }
+        if let Some(other) = urls.insert(shroom.url.to_lowercase(), &shroom.url) {
+            problems.push(error(
+                &shroom.url,
+                format!("url differs from {} only by case", other),
+            ));
+        }
+    }
+    problems
+}
+
+// `common_name: ''` and leaving the key out mean the same, so pick one
+fn common_names(shroompedia: &[ShroomInfo]) -> Vec<Problem> {
+    let (blank, missing): (Vec<&ShroomInfo>, Vec<&ShroomInfo>) = shroompedia
+        .iter()
+        .filter(|shroom| shroom.info.common_name.is_none())
+        .partition(|shroom| shroom.info.blank_common_name);
+    // on a tie neither is the odd one out, so the blanks are reported without saying
+    // which most do
+    let (odd_ones, style) = match blank.len().cmp(&missing.len()) {
+        Ordering::Less => (
+            blank,
+            "uses `common_name: ''` where most leave the key out".to_string(),
+        ),
+        Ordering::Greater => (
+            missing,
+            "leaves out `common_name` where most use `''`".to_string(),
+        ),
+        Ordering::Equal => {
+            let style = format!(
+                "uses `common_name: ''` while {} others leave the key out",
+                missing.len()
+            );
+            (blank, style)
+        }
+    };
+    odd_ones
+        .into_iter()
+        .map(|shroom| warning(&shroom.url, style.clone()))
+        .collect()
+}
+
+fn names(shroom: &ShroomInfo) -> Vec<Problem> {
+    let mut problems = vec![];
+    for segment in shroom.url.split('/').filter(|s| !s.is_empty()) {
+        if !segment.is_ascii() || segment.contains(|c: char| c.is_whitespace()) {
+            problems.push(error(
+                &shroom.url,
+                format!("url segment `{}` has non-ascii or whitespace", segment),
+            ));
+        }
+    }
+
+    let mut genus = shroom.gen.chars();
+    let genus_ok = genus.next().is_some_and(|c| c.is_ascii_uppercase())
+        && genus.all(|c| c.is_ascii_lowercase());
+    if !genus_ok {
+        problems.push(error(
+            &shroom.url,
+            format!(
+                "genus `{}` should be one capitalised latin word",
+                shroom.gen
+            ),
+        ));
+    }
+    for epithet in epithets(shroom) {
+        let epithet_ok = epithet.starts_with(|c: char| c.is_ascii_lowercase())
+            && epithet.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+            && !epithet.ends_with('-');
+        if !epithet_ok {
+            problems.push(error(
+                &shroom.url,
+                format!("epithet `{}` should be one lowercase latin word", epithet),
+            ));
+        }
+    }
+    problems
+}
+
+fn epithets(shroom: &ShroomInfo) -> Vec<&str> {
+    match shroom.info.rank {
+        Rank::Species => vec![&shroom.spe],
+        _ => vec![&shroom.spe, &shroom.info.name],
+    }
+}
+
+fn image_dir(url: &str) -> PathBuf {
+    PathBuf::from(IMAGE_DIR).join(url.trim_start_matches('/'))
+}
+
+// image directories that no species, subspecies, variety or form points at
+async fn orphans(shroompedia: &[ShroomInfo]) -> Vec<Problem> {
+    let known = shroompedia
+        .iter()
+        .map(|shroom| image_dir(&shroom.url))
+        .collect::<HashSet<PathBuf>>();
+    let mut problems = vec![];
+    let mut dirs = vec![PathBuf::from(IMAGE_DIR)];
+    while let Some(dir) = dirs.pop() {
+        let Ok(mut entries) = fs::read_dir(&dir).await else {
+            continue;
+        };
+        let mut has_images = false;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            match entry.file_type().await {
+                Ok(file_type) if file_type.is_dir() => dirs.push(entry.path()),
+                _ => has_images |= images::supported(&entry.file_name().to_string_lossy()),
+            }
+        }
+        if has_images && !known.contains(&dir) {
+            problems.push(warning(
+                &dir.to_string_lossy(),
+                "images with no yaml entry".to_string(),
+            ));
+        }
+    }
+    problems.sort_by(|a, b| a.at.cmp(&b.at));
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn common_name_warnings(names: &[Option<&str>]) -> Vec<String> {
+        let yaml = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| match name {
+                Some(name) => format!("    s{}:\n      common_name: '{}'\n", i, name),
+                None => format!("    s{}: {{}}\n", i),
+            })
+            .collect::<String>();
+        let (_, shroompedia) =
+            parse::from_str("test.yaml", &format!("a:\n  title: A\n  G:\n{}", yaml)).unwrap();
+        common_names(&shroompedia)
+            .iter()
+            .map(Problem::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn common_names_flag_the_fewer_style() {
+        assert_eq!(
+            common_name_warnings(&[Some(""), None, None, Some("Cep")]),
+            ["warning: /a/G/s0: uses `common_name: ''` where most leave the key out"]
+        );
+        assert_eq!(
+            common_name_warnings(&[Some(""), None, Some("")]),
+            ["warning: /a/G/s1: leaves out `common_name` where most use `''`"]
+        );
+        assert_eq!(
+            common_name_warnings(&[None, Some(""), None, Some("")]),
+            [
+                "warning: /a/G/s1: uses `common_name: ''` while 2 others leave the key out",
+                "warning: /a/G/s3: uses `common_name: ''` while 2 others leave the key out",
+            ]
+        );
+        assert!(common_name_warnings(&[None, Some("Cep")]).is_empty());
+    }
+}
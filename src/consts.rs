@@ -1,4 +1,7 @@
+use {crate::types::Signature, std::time::Duration};
+
 pub struct ReqFields {
+    pub accept_encoding: &'static str,
     pub ip: &'static str,
     pub referer: &'static str,
     pub user_agent: &'static str,
@@ -6,23 +9,104 @@ pub struct ReqFields {
 
 pub struct Paths {
     pub root: &'static str,
-    pub nf404: &'static str,
-    pub pd403: &'static str,
-    pub meta: &'static str,
-    pub menu: &'static str,
-    pub catpage: &'static str,
-    pub shroompage: &'static str,
-
-    pub frag_category: &'static str,
-    pub frag_genus: &'static str,
-    pub frag_species: &'static str,
-    pub frag_menu: &'static str,
+    pub templates: &'static str,
 }
 
 pub const YAML_FILE: &str = "/var/www/html/data/shroom_info.yaml";
 
+pub const DATA_DIR: &str = "/var/www/html/data/shroom_info";
+
+// csv export/import, one row per species, subspecies, variety or form
+pub const CSV_TAXON_COLUMNS: [&str; 10] = [
+    "category",
+    "title",
+    "genus",
+    "genus_author",
+    "class",
+    "order",
+    "family",
+    "species",
+    "rank",
+    "name",
+];
+
+pub const CSV_DETAIL_COLUMNS: [&str; 13] = [
+    "author",
+    "common_name",
+    "blurb",
+    "edibility",
+    "season",
+    "habitat",
+    "substrate",
+    "spore_print",
+    "cap",
+    "gills",
+    "stem",
+    "distribution",
+    "references",
+];
+
+// what the mycology host serves for urls that aren't generated pages
+pub const MYCOLOGY_ROOT: &str = "/var/www/html/mycology";
+
 pub const IMAGE_DIR: &str = "/var/www/html/mycology/Smallimages";
 
+pub const IMAGE_URL: &str = "/Smallimages";
+
+// resized copies of the species images, made by `rusty_website thumbnails`, in the
+// same directories as the originals
+pub const VARIANT_DIR: &str = "/var/www/html/mycology/variants";
+
+pub const VARIANT_URL: &str = "/variants";
+
+// thumbnail, medium and large
+pub const VARIANT_WIDTHS: [u32; 3] = [320, 800, 1600];
+
+pub const VARIANT_SIZES: &str = "(max-width: 640px) 100vw, 640px";
+
+// what the gallery picks up from a species' image directory
+pub const IMAGE_EXTENSIONS: [&str; 6] = ["jpg", "jpeg", "png", "webp", "gif", "avif"];
+
+// the ones of those that can be resized, there's no avif decoder
+pub const DECODABLE_EXTENSIONS: [&str; 5] = ["jpg", "jpeg", "png", "webp", "gif"];
+
+pub const AUTOINDEX_MARKER: &str = ".autoindex";
+
+pub const STATUS_PATH: &str = "/_status";
+
+pub const TAXON_URL: &str = "/taxon";
+
+pub const SEARCH_PATH: &str = "/search";
+
+pub const SEARCH_LIMIT: usize = 10;
+
+pub mod api {
+    pub const PATH: &str = "/api";
+    pub const CORS_ORIGIN: &str = "*";
+    pub const PAGE_SIZE: usize = 50;
+    pub const MAX_PAGE_SIZE: usize = 500;
+}
+
+// `/img/{path}?w=&fmt=` on the mycology host, resizing the originals on request
+pub mod img {
+    pub const PATH: &str = "/img";
+    pub const WIDTHS: [u32; 6] = [160, 320, 640, 800, 1280, 1600];
+    pub const CACHE_DIR: &str = "/var/www/html/data/img_cache";
+    pub const MAX_BYTES: u64 = 20 * 1024 * 1024;
+    pub const MAX_PIXELS: u64 = 50_000_000;
+    pub const CONCURRENT: usize = 2;
+}
+
+// per client, for requests that cost real work: BURST at once, then one per REFILL
+pub mod rate {
+    use std::time::Duration;
+
+    pub const BURST: u32 = 20;
+    pub const REFILL: Duration = Duration::from_secs(2);
+    // past this many remembered clients, the ones back to a full bucket are forgotten
+    pub const CLIENTS: usize = 10_000;
+}
+
 pub const LOG_FILE: &str = "/home/muesli/rusty_website.log";
 
 pub mod domains {
@@ -31,6 +115,7 @@ pub mod domains {
 }
 
 pub const FIELDS: ReqFields = ReqFields {
+    accept_encoding: "Accept-Encoding: ",
     ip: "X-Forwarded-For: ",
     referer: "Referer: ",
     user_agent: "User-Agent: ",
@@ -38,31 +123,130 @@ pub const FIELDS: ReqFields = ReqFields {
 
 pub mod status {
     pub const HTTP_200: &str = "HTTP/1.1 200 OK";
+    pub const HTTP_400: &str = "HTTP/1.1 400 BAD REQUEST";
     pub const HTTP_404: &str = "HTTP/1.1 404 NOT FOUND";
     pub const HTTP_403: &str = "HTTP/1.1 403 FORBIDDEN";
+    pub const HTTP_429: &str = "HTTP/1.1 429 TOO MANY REQUESTS";
+    pub const HTTP_503: &str = "HTTP/1.1 503 SERVICE UNAVAILABLE";
 }
 
 pub const PATH: Paths = Paths {
     root: "/var/www/html",
-    nf404: "/var/www/html/data/404.html",
-    pd403: "/var/www/html/data/403.html",
-    meta: "/var/www/html/data/meta.html",
-    menu: "/var/www/html/data/menu.html",
-    catpage: "/var/www/html/data/catpage.html",
-    shroompage: "/var/www/html/data/shroompage.html",
-
-    frag_category: "/var/www/html/data/fragments/category.html",
-    frag_genus: "/var/www/html/data/fragments/genus.html",
-    frag_species: "/var/www/html/data/fragments/species.html",
-    frag_menu: "/var/www/html/data/fragments/menu.html",
+    templates: "/var/www/html/data/templates",
 };
 
-pub const MIMETYPES: [(&str, &str); 7] = [
-    ("jpg", "image/jpeg"),
-    ("png", "image/png"),
+pub mod templates {
+    pub const NF404: &str = "404.html";
+    pub const PD403: &str = "403.html";
+    pub const MENU: &str = "menu.html";
+    pub const CATEGORY: &str = "category.html";
+    pub const SPECIES: &str = "species.html";
+    pub const TAXON: &str = "taxon.html";
+    pub const SEARCH: &str = "search.html";
+    pub const AUTOINDEX: &str = "autoindex.html";
+}
+
+pub const MIME_FILE: &str = "/var/www/html/data/mime.types";
+
+pub const MIMETYPES: [(&str, &str); 62] = [
     ("html", "text/html"),
-    ("txt", "text/plain"),
+    ("htm", "text/html"),
     ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("tsv", "text/tab-separated-values"),
+    ("ics", "text/calendar"),
+    ("vtt", "text/vtt"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("jsonld", "application/ld+json"),
+    ("webmanifest", "application/manifest+json"),
     ("xml", "application/xml"),
+    ("xhtml", "application/xhtml+xml"),
+    ("rss", "application/rss+xml"),
+    ("atom", "application/atom+xml"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("7z", "application/x-7z-compressed"),
+    ("wasm", "application/wasm"),
+    ("bin", "application/octet-stream"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("png", "image/png"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("svg", "image/svg+xml"),
+    ("ico", "image/x-icon"),
+    ("bmp", "image/bmp"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("heic", "image/heic"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("eot", "application/vnd.ms-fontobject"),
     ("mp4", "video/mp4"),
+    ("m4v", "video/mp4"),
+    ("webm", "video/webm"),
+    ("ogv", "video/ogg"),
+    ("mov", "video/quicktime"),
+    ("avi", "video/x-msvideo"),
+    ("mkv", "video/x-matroska"),
+    ("mp3", "audio/mpeg"),
+    ("m4a", "audio/mp4"),
+    ("ogg", "audio/ogg"),
+    ("oga", "audio/ogg"),
+    ("opus", "audio/opus"),
+    ("wav", "audio/wav"),
+    ("flac", "audio/flac"),
+    ("aac", "audio/aac"),
+    ("mid", "audio/midi"),
+    ("midi", "audio/midi"),
+];
+
+pub const CHARSET_TYPES: [&str; 6] = [
+    "application/json",
+    "application/ld+json",
+    "application/manifest+json",
+    "application/xml",
+    "application/yaml",
+    "image/svg+xml",
+];
+
+pub const MAGIC: [(Signature, &str); 18] = [
+    (&[(0, b"\x89PNG\r\n\x1a\n")], "image/png"),
+    (&[(0, b"\xff\xd8\xff")], "image/jpeg"),
+    (&[(0, b"GIF87a")], "image/gif"),
+    (&[(0, b"GIF89a")], "image/gif"),
+    (&[(0, b"RIFF"), (8, b"WEBP")], "image/webp"),
+    (&[(0, b"RIFF"), (8, b"AVI ")], "video/x-msvideo"),
+    (&[(0, b"RIFF"), (8, b"WAVE")], "audio/wav"),
+    (&[(4, b"ftypavif")], "image/avif"),
+    (&[(4, b"ftyp")], "video/mp4"),
+    (&[(0, b"\x00\x00\x01\x00")], "image/x-icon"),
+    (&[(0, b"BM"), (6, b"\0\0\0\0")], "image/bmp"),
+    (&[(0, b"%PDF-")], "application/pdf"),
+    (&[(0, b"PK\x03\x04")], "application/zip"),
+    (&[(0, b"\x1f\x8b")], "application/gzip"),
+    (&[(0, b"wOFF")], "font/woff"),
+    (&[(0, b"wOF2")], "font/woff2"),
+    (&[(0, b"\x1aE\xdf\xa3")], "video/webm"),
+    (&[(0, b"OggS")], "audio/ogg"),
 ];
+
+pub const WATCH_DEBOUNCE: Duration = Duration::from_millis(250);
+
+pub const WATCH_POLL: Duration = Duration::from_secs(2);
+
+pub const CACHE_BUDGET: usize = 64 * 1024 * 1024;
+
+pub const CACHE_MAX_FILE: u64 = 1024 * 1024;
@@ -0,0 +1,299 @@
+use {
+    crate::{
+        consts::{CSV_DETAIL_COLUMNS, CSV_TAXON_COLUMNS},
+        mycology::{
+            generate::{Rank, SpeInfo},
+            parse::{self, YamlError},
+        },
+        types::{Categories, Result},
+    },
+    serde_yaml::{Mapping, Value},
+    std::{collections::HashMap, iter, process::ExitCode},
+    tokio::fs,
+};
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    Json,
+    Csv,
+    Yaml,
+}
+
+impl Format {
+    pub fn from_arg(arg: Option<&str>, default: Format) -> Option<Format> {
+        match arg {
+            None => Some(default),
+            Some("json") => Some(Format::Json),
+            Some("csv") => Some(Format::Csv),
+            Some("yaml") => Some(Format::Yaml),
+            Some(_) => None,
+        }
+    }
+}
+
+// the live data, written to stdout
+pub async fn export(format: Format) -> Result<ExitCode> {
+    match parse::yaml().await {
+        Ok((categories, _)) => {
+            print!("{}", write(&categories, format)?);
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(e) => invalid(e),
+    }
+}
+
+// a json, yaml or csv file through the same validation as the yaml, written back out
+pub async fn import(file: &str, format: Format) -> Result<ExitCode> {
+    let contents = fs::read_to_string(file).await?;
+    let loaded = match file.ends_with(".csv") {
+        true => read_csv(file, &contents).and_then(|rows| parse::from_rows(file, rows)),
+        false => parse::from_str(file, &contents),
+    };
+    match loaded {
+        Ok((categories, _)) => {
+            print!("{}", write(&categories, format)?);
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(e) => invalid(e),
+    }
+}
+
+fn invalid(e: YamlError) -> Result<ExitCode> {
+    eprintln!("error: {}", e);
+    Ok(ExitCode::from(2))
+}
+
+fn write(categories: &Categories, format: Format) -> Result<String> {
+    Ok(match format {
+        Format::Json => serde_json::to_string_pretty(&document(categories))? + "\n",
+        Format::Yaml => serde_yaml::to_string(&document(categories))?,
+        Format::Csv => write_csv(categories)?,
+    })
+}
+
+// the same shape as shroom_info.yaml, leaving out whatever isn't set
+fn document(categories: &Categories) -> Value {
+    let mut document = Mapping::new();
+    for cat in categories {
+        let mut cat_map = Mapping::new();
+        cat_map.insert("title".into(), cat.menu_label.as_str().into());
+        for gen in &cat.genera {
+            let mut gen_map = Mapping::new();
+            set(&mut gen_map, "author", &gen.author);
+            for (rank, name) in gen.lineage.ranks() {
+                gen_map.insert(rank.into(), name.as_str().into());
+            }
+            for spe in &gen.species {
+                gen_map.insert(spe.name.as_str().into(), taxon(spe));
+            }
+            cat_map.insert(gen.name.as_str().into(), gen_map.into());
+        }
+        document.insert(cat.name.as_str().into(), cat_map.into());
+    }
+    document.into()
+}
+
+fn taxon(spe: &SpeInfo) -> Value {
+    let mut map = Mapping::new();
+    for (column, value) in details(spe) {
+        match (column, value) {
+            (_, None) => (),
+            ("season", Some(v)) => {
+                map.insert(column.into(), v.split(", ").collect());
+            }
+            ("references", Some(v)) => {
+                map.insert(column.into(), v.lines().collect());
+            }
+            (_, Some(v)) => {
+                map.insert(column.into(), v.into());
+            }
+        }
+    }
+    for (rank, key) in [
+        (Rank::Subspecies, "subspecies"),
+        (Rank::Variety, "varieties"),
+        (Rank::Form, "forms"),
+    ] {
+        let taxa = spe
+            .infraspecific
+            .iter()
+            .filter(|infra| infra.rank == rank)
+            .map(|infra| (Value::from(infra.name.as_str()), taxon(infra)))
+            .collect::<Mapping>();
+        if !taxa.is_empty() {
+            map.insert(key.into(), taxa.into());
+        }
+    }
+    map.into()
+}
+
+// CSV_DETAIL_COLUMNS in order, lists flattened the way the csv holds them
+fn details(spe: &SpeInfo) -> [(&'static str, Option<String>); 13] {
+    let list = |v: Vec<&str>, sep: &str| Some(v.join(sep)).filter(|v| !v.is_empty());
+    [
+        ("author", spe.author.clone()),
+        // `common_name: ''` survives as an empty one
+        (
+            "common_name",
+            spe.common_name
+                .clone()
+                .or_else(|| spe.blank_common_name.then(String::new)),
+        ),
+        ("blurb", Some(spe.blurb.clone()).filter(|v| !v.is_empty())),
+        ("edibility", spe.edibility.map(|e| e.as_str().to_string())),
+        ("season", list(spe.season.iter().map(|m| m.name()).collect(), ", ")),
+        ("habitat", spe.habitat.clone()),
+        ("substrate", spe.substrate.clone()),
+        ("spore_print", spe.spore_print.clone()),
+        ("cap", spe.cap.clone()),
+        ("gills", spe.gills.clone()),
+        ("stem", spe.stem.clone()),
+        ("distribution", spe.distribution.clone()),
+        ("references", list(spe.references.iter().map(String::as_str).collect(), "\n")),
+    ]
+}
+
+fn set(map: &mut Mapping, key: &str, value: &Option<String>) {
+    if let Some(value) = value {
+        map.insert(key.into(), value.as_str().into());
+    }
+}
+
+// a genus without species, or a category without genera, is a row with only those
+// columns; a blank common name is `''`, as in the yaml
+fn write_csv(categories: &Categories) -> Result<String> {
+    let mut csv = csv::Writer::from_writer(vec![]);
+    csv.write_record(CSV_TAXON_COLUMNS.iter().chain(CSV_DETAIL_COLUMNS.iter()))?;
+    for cat in categories {
+        if cat.genera.is_empty() {
+            placeholder(&mut csv, &[&cat.name, &cat.menu_label])?;
+        }
+        for gen in &cat.genera {
+            if gen.species.is_empty() {
+                placeholder(
+                    &mut csv,
+                    &[
+                        &cat.name,
+                        &cat.menu_label,
+                        &gen.name,
+                        gen.author.as_deref().unwrap_or_default(),
+                        gen.lineage.class.as_deref().unwrap_or_default(),
+                        gen.lineage.order.as_deref().unwrap_or_default(),
+                        gen.lineage.family.as_deref().unwrap_or_default(),
+                    ],
+                )?;
+            }
+            for spe in &gen.species {
+                let taxa = [(spe, "")]
+                    .into_iter()
+                    .chain(spe.infraspecific.iter().map(|infra| (infra, infra.name.as_str())));
+                for (taxon, name) in taxa {
+                    let taxonomy = [
+                        cat.name.as_str(),
+                        cat.menu_label.as_str(),
+                        gen.name.as_str(),
+                        gen.author.as_deref().unwrap_or_default(),
+                        gen.lineage.class.as_deref().unwrap_or_default(),
+                        gen.lineage.order.as_deref().unwrap_or_default(),
+                        gen.lineage.family.as_deref().unwrap_or_default(),
+                        spe.name.as_str(),
+                        taxon.rank.as_str(),
+                        name,
+                    ];
+                    let details = details(taxon).map(|(column, v)| match (column, v) {
+                        ("common_name", Some(v)) if v.is_empty() => "''".to_string(),
+                        (_, v) => v.unwrap_or_default(),
+                    });
+                    csv.write_record(
+                        taxonomy
+                            .iter()
+                            .copied()
+                            .chain(details.iter().map(String::as_str)),
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(String::from_utf8(csv.into_inner()?)?)
+}
+
+// the leading taxon columns, the rest left empty
+fn placeholder(csv: &mut csv::Writer<Vec<u8>>, cells: &[&str]) -> csv::Result<()> {
+    let columns = CSV_TAXON_COLUMNS.len() + CSV_DETAIL_COLUMNS.len();
+    csv.write_record(
+        cells
+            .iter()
+            .copied()
+            .chain(iter::repeat_n("", columns - cells.len())),
+    )
+}
+
+fn read_csv(
+    file: &str,
+    contents: &str,
+) -> std::result::Result<Vec<HashMap<String, String>>, YamlError> {
+    let mut csv = csv::Reader::from_reader(contents.as_bytes());
+    let invalid = |e: csv::Error| {
+        let line = e.position().map(|p| p.line() as usize);
+        YamlError {
+            file: file.to_string(),
+            line,
+            column: line.map(|_| 1),
+            message: e.to_string(),
+        }
+    };
+    let headers = csv.headers().map_err(invalid)?.clone();
+    csv.records()
+        .map(|record| {
+            let record = record.map_err(invalid)?;
+            Ok(headers
+                .iter()
+                .zip(record.iter())
+                .map(|(column, value)| (column.to_string(), value.to_string()))
+                .collect())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_round_trips() {
+        let yaml = "
+empty:
+  title: Nothing yet
+boletes:
+  title: Boletes
+  Suillus:
+    family: Suillaceae
+  Boletus:
+    author: Fr.
+    class: Agaricomycetes
+    edulis:
+      author: Bull.
+      common_name: Penny bun
+      blurb: 'Cap: brown'
+      edibility: choice
+      season: [aug, sep, oct]
+      references:
+        - Phillips, Mushrooms
+        - Sterry, Fungi
+      varieties:
+        pinophilus:
+          common_name: ''
+    badius:
+      common_name: ''
+";
+        let (categories, _) = parse::from_str("test.yaml", yaml).unwrap();
+        let csv = write(&categories, Format::Csv).unwrap();
+        let rows = read_csv("test.csv", &csv).unwrap();
+        let (imported, _) = parse::from_rows("test.csv", rows).unwrap();
+        assert_eq!(imported, categories);
+        assert!(imported[1].genera[1].species[1].blank_common_name);
+        assert!(imported[0].genera.is_empty());
+        assert!(imported[1].genera[0].species.is_empty());
+        assert_eq!(write(&imported, Format::Csv).unwrap(), csv);
+    }
+}
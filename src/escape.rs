@@ -0,0 +1,47 @@
+// html covers both text and quoted attribute values
+pub fn html(s: &str) -> String {
+    s.chars().fold(String::with_capacity(s.len()), |mut a, c| {
+        match c {
+            '&' => a.push_str("&amp;"),
+            '<' => a.push_str("&lt;"),
+            '>' => a.push_str("&gt;"),
+            '"' => a.push_str("&quot;"),
+            '\'' => a.push_str("&#39;"),
+            c => a.push(c),
+        };
+        a
+    })
+}
+
+pub fn url(s: &str) -> String {
+    s.bytes().fold(String::with_capacity(s.len()), |mut a, b| {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                a.push(b as char)
+            }
+            b => a.push_str(&format!("%{:02X}", b)),
+        };
+        a
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_escapes_markup_and_quotes() {
+        assert_eq!(
+            html(r#"<a href="x">Tom & 'Jerry'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+        );
+        assert_eq!(html("javascript:alert(1)"), "javascript:alert(1)");
+    }
+
+    #[test]
+    fn url_percent_encodes_everything_but_paths() {
+        assert_eq!(url("/fungi/Boletus edulis"), "/fungi/Boletus%20edulis");
+        assert_eq!(url(r#"<&"'>"#), "%3C%26%22%27%3E");
+        assert_eq!(url("javascript:alert(1)"), "javascript%3Aalert%281%29");
+    }
+}
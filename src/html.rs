@@ -1,195 +1,279 @@
 use {
     crate::{
-        consts::{self, IMAGE_DIR, PATH},
+        consts::{VARIANT_SIZES, VARIANT_URL},
+        escape,
         mycology::{
-            generate::{CatInfo, GenInfo, SpeInfo},
+            generate::{taxon_url, CatInfo, Filter, GenInfo, Lineage, Rank, SpeInfo},
+            images::{Encoding, Image},
             parse::{ShroomInfo, Shroompedia},
         },
-        types::{Categories, GenFold, Result, SpecFold},
+        types::{Categories, Images},
     },
-    std::fs,
+    minijinja::{context, Value},
 };
 
-pub fn menu(categories: &Categories, html_frag: &str) -> String {
-    categories.iter().fold(String::new(), |a, cat| {
-        format!(
-            "{}{}",
-            a,
-            html_frag
-                .replace("{HREF}", &cat.name)
-                .replace("{TITLE}", &cat.menu_label)
-        )
-    })
+pub fn menu(categories: &Categories, shroompedia: &Shroompedia) -> Value {
+    context! {
+        categories => categories
+            .iter()
+            .map(|cat| context! { name => cat.name, title => cat.menu_label })
+            .collect::<Vec<Value>>(),
+        search => flatten_names(shroompedia)
+            .into_iter()
+            .map(|(name, shroom)| context! { name, url => shroom.url, ..details(&shroom.info) })
+            .collect::<Vec<Value>>(),
+    }
 }
 
-fn flatten_names(shroompedia: &Shroompedia) -> Vec<(&String, &String)> {
+fn flatten_names(shroompedia: &Shroompedia) -> Vec<(&String, &ShroomInfo)> {
     shroompedia
         .iter()
-        .flat_map(|shroom| {
-            if shroom.info.common_name == "''" {
-                vec![(&shroom.latin_name, &shroom.url)]
-            } else {
-                vec![
-                    (&shroom.latin_name, &shroom.url),
-                    (&shroom.info.common_name, &shroom.url),
-                ]
-            }
+        .flat_map(|shroom| match &shroom.info.common_name {
+            Some(common_name) => vec![(&shroom.latin_name, shroom), (common_name, shroom)],
+            None => vec![(&shroom.latin_name, shroom)],
         })
         .collect()
 }
 
-pub fn search_opts(shroompedia: &Shroompedia) -> String {
-    format!(
-        r#"<div>
-      <datalist id="suggestions">
-          {}
-      </datalist>
-      <form method="dialog" id="form" onSubmit="doSearch()">
-        <input id="query" placeholder="Search..." aria-label="Search" list="suggestions" autocomplete="off"> 
-        <input type="submit" value="Search Shrooms...">
-      </form>
-    </div>"#,
-        shroompedia
-            .iter()
-            .flat_map(|shroom| if shroom.info.common_name == "''" {
-                vec![&shroom.latin_name]
-            } else {
-                [vec![&shroom.latin_name], vec![&shroom.info.common_name]].concat()
-            })
-            .fold(String::new(), |a, name| format!(
-                "{}<option>{}</option>\n",
-                a, name
-            ))
-    )
+fn details(spe: &SpeInfo) -> Value {
+    context! {
+        edibility => spe.edibility.map(|e| e.as_str()),
+        season => spe.season.iter().map(|m| m.name()).collect::<Vec<&str>>(),
+        habitat => spe.habitat,
+        substrate => spe.substrate,
+        spore_print => spe.spore_print,
+        cap => spe.cap,
+        gills => spe.gills,
+        stem => spe.stem,
+        distribution => spe.distribution,
+        references => spe.references,
+    }
 }
 
-pub fn search_obj(shroompedia: &Shroompedia) -> String {
-    flatten_names(shroompedia)
-        .into_iter()
-        .fold(String::new(), |a, (spec, url)| {
-            format!(r#"{}"{}":"{}","#, a, spec, url,)
-        })
+fn crumb(name: &str, url: &str) -> Value {
+    context! { name, url }
 }
 
-struct HtmlFrags<'f> {
-    category: &'f str,
-    genus: &'f str,
-    species: &'f str,
+fn lineage(lineage: &Lineage) -> Vec<Value> {
+    lineage
+        .ranks()
+        .into_iter()
+        .map(|(rank, name)| context! { rank, name, url => taxon_url(rank, name) })
+        .collect()
 }
 
 impl ShroomInfo {
-    pub fn htmlify(&self) -> Result<String> {
-        let html_frag = &from_file(PATH.frag_species)?;
-        let path = format!("{}/{}", IMAGE_DIR, self.url);
-        Ok((0..count_dir(path)).fold(String::new(), |a, n| {
-            format!(
-                "{}{}",
-                a,
-                html_frag.replace(
-                    "{PATH}",
-                    &format!(
-                        "{}/{}/{}/{}{}{}.jpg",
-                        self.cat, self.gen, self.spe, self.gen, self.spe, n
-                    )
-                )
-            )
-        }))
+    pub fn context(&self, categories: &Categories, images: &Images) -> Value {
+        let cat_url = format!("/{}", self.cat);
+        let gen_url = format!("{}/{}", cat_url, self.gen);
+        let spe_url = format!("{}/{}", gen_url, self.spe);
+        let mut breadcrumbs = vec![
+            crumb("Home", "/"),
+            match categories.iter().find(|cat| cat.name == self.cat) {
+                Some(cat) => crumb(&cat.menu_label, &cat_url),
+                None => crumb(&self.cat, &cat_url),
+            },
+            crumb(&self.gen, &gen_url),
+        ];
+        if self.info.rank != Rank::Species {
+            breadcrumbs.push(crumb(&self.spe, &spe_url));
+        }
+        context! {
+            latin_name => self.latin_name,
+            rank => self.info.rank.as_str(),
+            author => self.info.author,
+            common_name => self.info.common_name,
+            blurb => self.info.blurb,
+            url => self.url,
+            category => self.cat,
+            breadcrumbs,
+            lineage => lineage(&self.lineage),
+            infraspecific => infraspecific(&self.info, &self.url),
+            images => gallery(images, &self.url, &self.latin_name),
+            ..details(&self.info)
+        }
     }
 }
 
-impl CatInfo {
-    pub fn htmlify(&self) -> Result<String> {
-        let html_frags = HtmlFrags {
-            category: &from_file(PATH.frag_category)?,
-            genus: &from_file(PATH.frag_genus)?,
-            species: &from_file(PATH.frag_species)?,
-        };
-        self.genera.iter().try_fold(String::new(), move |a, genus| {
-            let genus_html = genus.species.iter().fold(
-                String::new(),
-                GenInfo::htmlify(&self.name, &genus.name, &html_frags),
-            );
-            Ok(format!(
-                "{}{}",
-                a,
-                html_frags
-                    .category
-                    .replace("{TITLE}", &genus.name)
-                    .replace("{HTML}", &genus_html)
-            ))
+fn infraspecific(spe: &SpeInfo, url: &str) -> Vec<Value> {
+    spe.infraspecific
+        .iter()
+        .map(|infra| {
+            context! {
+                name => infra.name,
+                rank => infra.rank.as_str(),
+                abbreviation => infra.rank.abbreviation(),
+                author => infra.author,
+                common_name => infra.common_name,
+                url => format!(
+                    "{}/{}/{}",
+                    url,
+                    infra.rank.abbreviation().trim_end_matches('.'),
+                    infra.name
+                ),
+            }
         })
+        .collect()
+}
+
+fn genus(cat: &CatInfo, genus: &GenInfo, filter: &Filter, images: &Images) -> Option<Value> {
+    let url = format!("/{}/{}", cat.name, genus.name);
+    let mut species = vec![];
+    for spe in genus.species.iter().filter(|spe| filter.matches(spe)) {
+        let spe_url = format!("{}/{}", url, spe.name);
+        species.push(context! {
+            name => spe.name,
+            author => spe.author,
+            common_name => spe.common_name,
+            blurb => spe.blurb,
+            infraspecific => infraspecific(spe, &spe_url),
+            images => gallery(images, &spe_url, &format!("{} {}", genus.name, spe.name)),
+            url => spe_url,
+            ..details(spe)
+        });
     }
+    (!species.is_empty()).then(|| {
+        context! {
+            name => genus.name,
+            author => genus.author,
+            url,
+            species,
+        }
+    })
 }
 
-impl GenInfo {
-    fn htmlify<'g>(
-        category: &'g str,
-        genus: &'g str,
-        html_frags: &'g HtmlFrags<'g>,
-    ) -> GenFold<'g> {
-        Box::new(move |a, species| {
-            let name = format!(
-                "{}{}",
-                species.name,
-                if species.common_name == "''" {
-                    String::new()
-                } else {
-                    format!(" - {}", species.common_name)
-                }
-            );
-            let path = [consts::IMAGE_DIR, category, genus, &species.name].join("/");
-            let species_html = (0..count_dir(path)).fold(
-                String::new(),
-                SpeInfo::htmlify(category, genus, &species.name, html_frags),
-            );
-            format!(
-                "{}{}",
-                a,
-                html_frags
-                    .genus
-                    .replace("{NAME}", &name)
-                    .replace("{BLURB}", &species.blurb)
-                    .replace("{HTML}", &species_html)
-            )
-        })
+impl CatInfo {
+    pub fn context(&self, filter: &Filter, images: &Images) -> Value {
+        let genera = self
+            .genera
+            .iter()
+            .filter_map(|gen| genus(self, gen, filter, images))
+            .collect::<Vec<Value>>();
+        context! {
+            name => self.name,
+            title => self.menu_label,
+            breadcrumbs => vec![crumb("Home", "/")],
+            genera,
+            filter => context! {
+                edibility => filter.edibility.map(|e| e.as_str()),
+                month => filter.month.map(|m| m.name()),
+                habitat => filter.habitat,
+                spore_print => filter.spore_print,
+            },
+        }
     }
 }
 
-impl SpeInfo {
-    fn htmlify<'s>(
-        category: &'s str,
-        genus: &'s str,
-        species: &'s str,
-        html_frags: &'s HtmlFrags<'s>,
-    ) -> SpecFold<'s> {
-        Box::new(move |a, n| {
-            let path = format!(
-                "{}/{}/{}/{}{}{}.jpg",
-                category, genus, species, genus, species, n
-            );
-            format!("{}{}", a, html_frags.species.replace("{PATH}", &path))
-        })
+impl GenInfo {
+    pub fn context(&self, cat: &CatInfo, images: &Images) -> Value {
+        context! {
+            rank => "genus",
+            name => self.name,
+            author => self.author,
+            breadcrumbs => vec![
+                crumb("Home", "/"),
+                crumb(&cat.menu_label, &format!("/{}", cat.name)),
+            ],
+            lineage => lineage(&self.lineage),
+            children => Vec::<Value>::new(),
+            genera => genus(cat, self, &Filter::default(), images).into_iter().collect::<Vec<_>>(),
+        }
     }
 }
-fn count_dir(path: String) -> usize {
-    match fs::read_dir(&path) {
-        Ok(v) => v
-            .filter(|f| {
-                f.as_ref().is_ok_and(|f| {
-                    f.file_name()
-                        .into_string()
-                        .unwrap_or_default()
-                        .ends_with(".jpg")
-                })
-            })
-            .count(),
-        Err(e) => {
-            eprintln!("{} {}", e, path,);
-            0
+
+// a class, order or family page, or None if nothing in the data has it
+pub fn taxon(categories: &Categories, images: &Images, rank: &str, name: &str) -> Option<Value> {
+    let members = categories
+        .iter()
+        .flat_map(|cat| cat.genera.iter().map(move |gen| (cat, gen)))
+        .filter(|(_, gen)| gen.lineage.get(rank).is_some_and(|v| v == name))
+        .collect::<Vec<_>>();
+    let (_, first) = members.first()?;
+
+    let mut breadcrumbs = vec![crumb("Home", "/")];
+    breadcrumbs.extend(
+        first
+            .lineage
+            .ranks()
+            .into_iter()
+            .take_while(|(r, _)| *r != rank)
+            .map(|(r, n)| crumb(n, &taxon_url(r, n))),
+    );
+
+    // the next rank down that's given, falling through to the genera themselves
+    let mut children: Vec<Value> = vec![];
+    for (_, gen) in &members {
+        let child = Lineage::RANKS
+            .iter()
+            .skip_while(|r| **r != rank)
+            .skip(1)
+            .find_map(|r| Some((*r, gen.lineage.get(r)?)));
+        if let Some((r, n)) = child {
+            let child = context! { rank => r, name => n, url => taxon_url(r, n) };
+            if !children.contains(&child) {
+                children.push(child);
+            }
         }
     }
+
+    let genera = members
+        .iter()
+        .filter_map(|(cat, gen)| genus(cat, gen, &Filter::default(), images))
+        .collect::<Vec<Value>>();
+    Some(context! {
+        rank,
+        name,
+        breadcrumbs,
+        lineage => Vec::<Value>::new(),
+        children,
+        genera,
+    })
 }
-pub fn from_file(path: &'static str) -> Result<String> {
-    let meta = &fs::read_to_string(consts::PATH.meta)?;
-    Ok(fs::read_to_string(path).map(|v| v.replace("{META}", meta))?)
+
+pub fn images<'a>(images: &'a Images, url: &str) -> &'a [Image] {
+    images.get(url).map(Vec::as_slice).unwrap_or_default()
+}
+
+// each image with a srcset per encoding it has resized copies in, and what its
+// sidecar says about it; alt text falls back to the name of what's pictured
+fn gallery(images: &Images, url: &str, name: &str) -> Vec<Value> {
+    let images = self::images(images, url);
+    images
+        .iter()
+        .enumerate()
+        .map(|(i, image)| {
+            let sources = Encoding::VARIANTS
+                .iter()
+                .filter_map(|encoding| {
+                    let srcset = image
+                        .variants
+                        .iter()
+                        .filter(|(_, e)| e == encoding)
+                        .map(|(width, _)| {
+                            let copy = image.variant(*width, *encoding);
+                            format!("{}/{} {}w", VARIANT_URL, escape::url(&copy), width)
+                        })
+                        .collect::<Vec<String>>();
+                    (!srcset.is_empty()).then(|| {
+                        context! { type => encoding.mime_type(), srcset => srcset.join(", ") }
+                    })
+                })
+                .collect::<Vec<Value>>();
+            let metadata = &image.metadata;
+            let alt = match (&metadata.alt, images.len()) {
+                (Some(alt), _) => alt.clone(),
+                (None, 1) => name.to_string(),
+                (None, count) => format!("{}, photo {} of {}", name, i + 1, count),
+            };
+            context! {
+                path => image.path,
+                sources,
+                sizes => VARIANT_SIZES,
+                alt,
+                caption => metadata.caption,
+                credit => metadata.credit,
+                licence => metadata.licence,
+            }
+        })
+        .collect()
 }
@@ -1,6 +1,13 @@
+pub mod check;
+pub mod convert;
 mod consts;
+mod escape;
 mod html;
 mod log;
+mod mime;
 mod mycology;
+pub mod render;
 pub mod server;
+mod template;
+pub mod thumbnails;
 pub mod types;
@@ -1,7 +1,8 @@
 use {
   crate::{
     consts::{self, domains},
-    server::response::Host,
+    mycology::parse::YamlError,
+    server::{cache::Stats, response::Host},
     types::IpAddr,
   },
   std::time,
@@ -23,6 +24,7 @@ pub struct Log {
   pub start_time: time::SystemTime,
   pub unique_cxn: u64,
   pub total_cxn: u64,
+  pub cache: Stats,
 }
 
 impl Log {
@@ -40,6 +42,7 @@ impl Log {
       start_time,
       unique_cxn,
       total_cxn,
+      cache,
     } = self;
 
     let ip_str = ip.to_string();
@@ -50,6 +53,12 @@ impl Log {
     let referer = referer.unwrap_or_else(none);
     let user_agent = user_agent.unwrap_or_else(none);
     let turnaround = cxn_time.to_elapsed();
+    let Stats {
+      hits,
+      misses,
+      entries,
+      bytes,
+    } = cache;
 
     let mini_log = |total_cxn: u64| {
       format!(
@@ -64,6 +73,7 @@ impl Log {
             # Unique: {unique_cxn}\n\
             # Total: {total_cxn}\n\
             Up-time:{uptime}\n\
+            Cache: {hits} hits - {misses} misses - {entries} files - {bytes}b\n\
             Request:\n\
             \tPath: {path}\n\
             \tHost: {host}\n\
@@ -84,6 +94,27 @@ impl Log {
   }
 }
 
+pub fn yaml_error(e: &YamlError) -> String {
+  let none = || "None".to_owned();
+  let timestamp = time::SystemTime::now().to_string();
+  let YamlError {
+    file,
+    line,
+    column,
+    message,
+  } = e;
+  let line = line.map(|v| v.to_string()).unwrap_or_else(none);
+  let column = column.map(|v| v.to_string()).unwrap_or_else(none);
+  format!(
+    "YAML RELOAD FAILED - SERVING LAST GOOD DATA\n\
+        Timestamp: {timestamp}\n\
+        File: {file}\n\
+        Line: {line}\n\
+        Column: {column}\n\
+        Error: {message}\n"
+  )
+}
+
 trait ToString {
   fn to_string(self) -> String;
 }
@@ -1,6 +1,39 @@
-use rusty_website::types::Result;
+use {
+  rusty_website::{
+    convert::{self, Format},
+    types::Result,
+  },
+  std::process::ExitCode,
+};
+
+const USAGE: &str = "usage: rusty_website [check [--strict] | export [json|csv|yaml] | import <file> [yaml|json|csv] | render-all <dir> | thumbnails]";
+
 #[tokio::main]
-async fn main() -> Result<()> {
-  rusty_website::server::run::start_server().await?;
-  Ok(())
+async fn main() -> Result<ExitCode> {
+  let args = std::env::args().skip(1).collect::<Vec<String>>();
+  let args = args.iter().map(String::as_str).collect::<Vec<&str>>();
+  match args[..] {
+    [] => {
+      rusty_website::server::run::start_server().await?;
+      Ok(ExitCode::SUCCESS)
+    }
+    ["check"] => Ok(rusty_website::check::run(false).await),
+    ["check", "--strict"] => Ok(rusty_website::check::run(true).await),
+    ["export", ..] if args.len() <= 2 => match Format::from_arg(args.get(1).copied(), Format::Json) {
+      Some(format) => convert::export(format).await,
+      None => usage(),
+    },
+    ["import", file, ..] if args.len() <= 3 => match Format::from_arg(args.get(2).copied(), Format::Yaml) {
+      Some(format) => convert::import(file, format).await,
+      None => usage(),
+    },
+    ["render-all", dir] => rusty_website::render::run(dir).await,
+    ["thumbnails"] => rusty_website::thumbnails::run().await,
+    _ => usage(),
+  }
+}
+
+fn usage() -> Result<ExitCode> {
+  eprintln!("{}", USAGE);
+  Ok(ExitCode::from(2))
 }
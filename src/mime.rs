@@ -0,0 +1,164 @@
+use {
+    crate::consts::{CHARSET_TYPES, MAGIC, MIMETYPES, MIME_FILE},
+    std::{collections::HashMap, io, sync::OnceLock},
+    tokio::fs,
+};
+
+static REGISTRY: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+// the built-in types plus MIME_FILE's overrides, read once at startup so no request
+// waits on the disk for it
+pub async fn load() {
+    let overrides = match fs::read_to_string(MIME_FILE).await {
+        Ok(v) => parse_overrides(&v),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+        Err(e) => {
+            eprintln!("{} {} - cannot read mime type overrides", e, MIME_FILE);
+            vec![]
+        }
+    };
+    if REGISTRY.set(build(overrides)).is_err() {
+        eprintln!("mime types already loaded - {} ignored", MIME_FILE);
+    }
+}
+
+// the built-ins alone when load() hasn't run
+fn registry() -> &'static HashMap<String, String> {
+    REGISTRY.get_or_init(|| build(vec![]))
+}
+
+fn build(overrides: Vec<(String, String)>) -> HashMap<String, String> {
+    MIMETYPES
+        .into_iter()
+        .map(|(ext, mime)| (ext.to_string(), mime.to_string()))
+        .chain(overrides)
+        .map(|(ext, mime)| (ext.to_lowercase(), with_charset(mime)))
+        .collect()
+}
+
+// same layout as /etc/mime.types - "type/subtype ext1 ext2 ..."
+fn parse_overrides(file: &str) -> Vec<(String, String)> {
+    file.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .flat_map(|l| {
+            let mut words = l.split_whitespace();
+            let mime = words.next().unwrap_or_default();
+            words
+                .map(|ext| (ext.trim_start_matches('.').to_string(), mime.to_string()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn with_charset(mime: String) -> String {
+    if !mime.contains(';') && (mime.starts_with("text/") || CHARSET_TYPES.contains(&mime.as_str()))
+    {
+        format!("{}; charset=utf-8", mime)
+    } else {
+        mime
+    }
+}
+
+pub fn from_ext(file: &str) -> Option<&'static str> {
+    let (_, name) = file.rsplit_once('/').unwrap_or(("", file));
+    let (_, ext) = name.rsplit_once('.')?;
+    registry().get(&ext.to_lowercase()).map(String::as_str)
+}
+
+pub fn from_content(file: &str, content: &[u8]) -> &'static str {
+    from_ext(file).unwrap_or_else(|| sniff(content))
+}
+
+pub fn sniff(content: &[u8]) -> &'static str {
+    if let Some((_, mime)) = MAGIC.into_iter().find(|(signature, _)| {
+        signature.iter().all(|(offset, magic)| {
+            content
+                .get(*offset..offset + magic.len())
+                .is_some_and(|v| v == *magic)
+        })
+    }) {
+        return mime;
+    }
+
+    let head = &content[..content.len().min(512)];
+    let text = head.trim_ascii_start().to_ascii_lowercase();
+    if text.starts_with(b"<!doctype html") || text.starts_with(b"<html") {
+        "text/html; charset=utf-8"
+    } else if text.starts_with(b"<svg") || text.starts_with(b"<?xml") && contains(&text, b"<svg") {
+        "image/svg+xml; charset=utf-8"
+    } else if text.starts_with(b"<?xml") {
+        "application/xml; charset=utf-8"
+    } else if is_text(head) {
+        "text/plain; charset=utf-8"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+fn is_text(head: &[u8]) -> bool {
+    let valid_utf8 = match std::str::from_utf8(head) {
+        Ok(_) => true,
+        // a multibyte char cut off by the 512 byte window is still text
+        Err(e) => e.error_len().is_none(),
+    };
+    valid_utf8
+        && !head
+            .iter()
+            .any(|b| b.is_ascii_control() && !b"\t\n\r\x0c".contains(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn riff_by_its_form_type() {
+        assert_eq!(sniff(b"RIFF\x24\0\0\0WEBPVP8L"), "image/webp");
+        assert_eq!(sniff(b"RIFF\x24\0\0\0AVI LIST"), "video/x-msvideo");
+        assert_eq!(sniff(b"RIFF\x24\0\0\0WAVEfmt "), "audio/wav");
+        // neither RIFF nor text
+        assert_eq!(sniff(b"RIFX\x24\0\0\0WEBP"), "application/octet-stream");
+    }
+
+    #[test]
+    fn bmp_needs_its_reserved_zeros() {
+        assert_eq!(sniff(b"BM\x36\0\x0c\0\0\0\0\0\x36\0"), "image/bmp");
+        assert_eq!(sniff(b"BMW and other cars"), "text/plain; charset=utf-8");
+    }
+
+    #[test]
+    fn short_buffers_dont_match_longer_signatures() {
+        assert_eq!(sniff(b""), "text/plain; charset=utf-8");
+        assert_eq!(sniff(b"RIFF"), "text/plain; charset=utf-8");
+        assert_eq!(sniff(b"RIFF\0\0\0\0WEB"), "application/octet-stream");
+        assert_eq!(sniff(b"\x89PNG"), "application/octet-stream");
+    }
+
+    #[test]
+    fn text_or_binary_fallback() {
+        assert_eq!(sniff(b"  <!DOCTYPE html><p>"), "text/html; charset=utf-8");
+        assert_eq!(
+            sniff(b"<?xml version=\"1.0\"?><svg>"),
+            "image/svg+xml; charset=utf-8"
+        );
+        assert_eq!(
+            sniff(b"<?xml version=\"1.0\"?><feed>"),
+            "application/xml; charset=utf-8"
+        );
+        assert_eq!(
+            sniff("caf\u{e9}\tna\u{ef}ve\n".as_bytes()),
+            "text/plain; charset=utf-8"
+        );
+        // a multibyte char cut off by the window is still text
+        let mut cut = "a".repeat(511).into_bytes();
+        cut.extend("\u{e9}".as_bytes());
+        assert_eq!(sniff(&cut), "text/plain; charset=utf-8");
+        assert_eq!(sniff(b"\0\x01\x02\x03"), "application/octet-stream");
+        assert_eq!(sniff(b"\xff\xfe\xfd"), "application/octet-stream");
+    }
+}
@@ -1,2 +1,8 @@
+pub mod api;
 pub mod generate;
+pub mod images;
+pub mod img;
+pub mod index;
+pub mod pages;
 pub mod parse;
+pub mod search;
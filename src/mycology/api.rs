@@ -0,0 +1,243 @@
+use {
+    super::{
+        generate::{CatInfo, Filter, GenInfo, Lineage, SpeInfo},
+        parse::{ShroomInfo, Shroompedia},
+    },
+    crate::{
+        consts::{api, status, IMAGE_URL},
+        escape, html,
+        server::{
+            request::{self, GetParam},
+            response::Response,
+        },
+        types::{Categories, Images, Mycology, Query, Result},
+    },
+    serde_json::{json, Map, Value},
+};
+
+// `/api` and below, but not a category that happens to start with it
+pub fn handles(path: &str) -> bool {
+    path.strip_prefix(api::PATH)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '?']))
+}
+
+pub async fn get(
+    (categories, shroompedia, index, images): &Mycology,
+    request: &str,
+) -> Result<Response> {
+    let (request, query) = request::split_query(request);
+    let Some(endpoint) = request.strip_prefix(api::PATH) else {
+        return Ok(not_found("unknown endpoint"));
+    };
+    let fields = query.get_param("fields").map(|fields| {
+        fields
+            .split(',')
+            .map(|field| field.trim().to_string())
+            .filter(|field| !field.is_empty())
+            .collect::<Vec<String>>()
+    });
+    let fields = fields.as_deref();
+
+    Ok(match endpoint.trim_end_matches('/') {
+        "/species" => {
+            let filter = Filter::from_query(&query);
+            // `?q=` narrows to full-text matches, best first
+            let matching = match query.get_param("q") {
+                Some(q) => index
+                    .search(q)
+                    .into_iter()
+                    .map(|(id, _)| &shroompedia[id])
+                    .collect::<Vec<&ShroomInfo>>(),
+                None => shroompedia.iter().collect(),
+            }
+            .into_iter()
+            .filter(|shroom| filter.matches(&shroom.info))
+            .collect::<Vec<&ShroomInfo>>();
+            let (page, items) = paginate(&query, &matching);
+            let data = items
+                .iter()
+                .map(|shroom| select(species(shroom, images), fields))
+                .collect::<Vec<Value>>();
+            ok(json!({ "data": data, "pagination": page }))
+        }
+        "/categories" => {
+            let (page, items) = paginate(&query, categories);
+            let data = items.iter().map(category).collect::<Vec<Value>>();
+            ok(json!({ "data": data, "pagination": page }))
+        }
+        endpoint => {
+            if let Some(url) = endpoint.strip_prefix("/species") {
+                match shroompedia.iter().find(|shroom| shroom.url == url) {
+                    Some(shroom) => ok(json!({ "data": select(species(shroom, images), fields) })),
+                    None => not_found("no such species"),
+                }
+            } else if let Some(name) = endpoint.strip_prefix("/genera/") {
+                match genus(categories, shroompedia, images, name, fields) {
+                    Some(data) => ok(json!({ "data": data })),
+                    None => not_found("no such genus"),
+                }
+            } else {
+                not_found("unknown endpoint")
+            }
+        }
+    })
+}
+
+fn ok(body: Value) -> Response {
+    response(status::HTTP_200, body)
+}
+
+fn not_found(message: &str) -> Response {
+    response(status::HTTP_404, json!({ "error": message }))
+}
+
+fn response(status: &'static str, body: Value) -> Response {
+    Response {
+        status,
+        mime_type: "application/json",
+        headers: vec![
+            ("Access-Control-Allow-Origin", api::CORS_ORIGIN.to_string()),
+            ("Access-Control-Allow-Methods", "GET, OPTIONS".to_string()),
+            ("Vary", "Origin".to_string()),
+        ],
+        content: body.to_string().into_bytes(),
+    }
+}
+
+// `?page=` counts from 1, `?per_page=` is capped at MAX_PAGE_SIZE
+fn paginate<'a, T>(query: &Query, items: &'a [T]) -> (Value, &'a [T]) {
+    let param = |key: &str| query.get_param(key).and_then(|v| v.parse::<usize>().ok());
+    let per_page = param("per_page")
+        .unwrap_or(api::PAGE_SIZE)
+        .clamp(1, api::MAX_PAGE_SIZE);
+    let pages = items.len().div_ceil(per_page).max(1);
+    let page = param("page").unwrap_or(1).clamp(1, pages);
+    let start = ((page - 1) * per_page).min(items.len());
+    let end = (start + per_page).min(items.len());
+    (
+        json!({
+            "page": page,
+            "per_page": per_page,
+            "pages": pages,
+            "total": items.len(),
+        }),
+        &items[start..end],
+    )
+}
+
+// `?fields=latin_name,url` keeps just those keys of each species
+fn select(species: Value, fields: Option<&[String]>) -> Value {
+    match (species, fields) {
+        (Value::Object(object), Some(fields)) => Value::Object(
+            object
+                .into_iter()
+                .filter(|(key, _)| fields.contains(key))
+                .collect::<Map<String, Value>>(),
+        ),
+        (species, _) => species,
+    }
+}
+
+fn lineage(lineage: &Lineage) -> Value {
+    json!({
+        "class": lineage.class,
+        "order": lineage.order,
+        "family": lineage.family,
+    })
+}
+
+fn species(shroom: &ShroomInfo, images: &Images) -> Value {
+    let spe = &shroom.info;
+    let images = html::images(images, &shroom.url)
+        .iter()
+        .map(|image| format!("{}/{}", IMAGE_URL, escape::url(&image.path)))
+        .collect::<Vec<String>>();
+    json!({
+        "latin_name": shroom.latin_name,
+        "rank": spe.rank.as_str(),
+        "author": spe.author,
+        "common_name": spe.common_name,
+        "category": shroom.cat,
+        "genus": shroom.gen,
+        "species": shroom.spe,
+        "lineage": lineage(&shroom.lineage),
+        "blurb": spe.blurb,
+        "edibility": spe.edibility.map(|e| e.as_str()),
+        "season": spe.season.iter().map(|m| m.name()).collect::<Vec<&str>>(),
+        "habitat": spe.habitat,
+        "substrate": spe.substrate,
+        "spore_print": spe.spore_print,
+        "cap": spe.cap,
+        "gills": spe.gills,
+        "stem": spe.stem,
+        "distribution": spe.distribution,
+        "references": spe.references,
+        "infraspecific": infraspecific(spe, &shroom.url),
+        "images": images,
+        "url": shroom.url,
+        "api_url": format!("{}/species{}", api::PATH, shroom.url),
+    })
+}
+
+fn infraspecific(spe: &SpeInfo, url: &str) -> Vec<Value> {
+    spe.infraspecific
+        .iter()
+        .map(|infra| {
+            let url = format!(
+                "{}/{}/{}",
+                url,
+                infra.rank.abbreviation().trim_end_matches('.'),
+                infra.name
+            );
+            json!({
+                "name": infra.name,
+                "rank": infra.rank.as_str(),
+                "api_url": format!("{}/species{}", api::PATH, url),
+                "url": url,
+            })
+        })
+        .collect()
+}
+
+fn category(cat: &CatInfo) -> Value {
+    json!({
+        "name": cat.name,
+        "title": cat.menu_label,
+        "genera": cat.genera.iter().map(|gen| {
+            json!({
+                "name": gen.name,
+                "species": gen.species.len(),
+                "api_url": format!("{}/genera/{}", api::PATH, gen.name),
+            })
+        }).collect::<Vec<Value>>(),
+        "url": format!("/{}", cat.name),
+    })
+}
+
+// a genus can turn up under more than one category, this merges them
+fn genus(
+    categories: &Categories,
+    shroompedia: &Shroompedia,
+    images: &Images,
+    name: &str,
+    fields: Option<&[String]>,
+) -> Option<Value> {
+    let found = categories
+        .iter()
+        .flat_map(|cat| cat.genera.iter().map(move |gen| (cat, gen)))
+        .filter(|(_, gen)| gen.name == name)
+        .collect::<Vec<(&CatInfo, &GenInfo)>>();
+    let (_, first) = found.first()?;
+    let species = shroompedia
+        .iter()
+        .filter(|shroom| shroom.gen == name)
+        .map(|shroom| select(self::species(shroom, images), fields))
+        .collect::<Vec<Value>>();
+    Some(json!({
+        "name": first.name,
+        "author": first.author,
+        "lineage": lineage(&first.lineage),
+        "categories": found.iter().map(|(cat, _)| &cat.name).collect::<Vec<&String>>(),
+        "species": species,
+    }))
+}
@@ -1,117 +1,312 @@
 use {
-    super::parse::{ShroomInfo, Shroompedia},
+    super::search,
     crate::{
-        consts, html,
-        server::response::Response,
-        types::{Categories, Content, Result},
+        consts::{self, templates},
+        html,
+        server::{
+            request::{self, GetParam},
+            response::Response,
+        },
+        template,
+        types::{Mycology, Query, Result},
     },
-    std::io,
+    minijinja::context,
+    serde::Deserialize,
 };
+#[cfg_attr(test, derive(Debug, PartialEq))]
 pub struct CatInfo {
     pub name: String,
     pub menu_label: String,
     pub genera: Vec<GenInfo>,
 }
 
+#[cfg_attr(test, derive(Debug, PartialEq))]
 pub struct GenInfo {
     pub name: String,
+    pub author: Option<String>,
+    pub lineage: Lineage,
     pub species: Vec<SpeInfo>,
 }
 
+// the ranks above genus, any of which may be left out of the yaml
+#[derive(Clone, Default)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct Lineage {
+    pub class: Option<String>,
+    pub order: Option<String>,
+    pub family: Option<String>,
+}
+
+impl Lineage {
+    pub const RANKS: [&'static str; 3] = ["class", "order", "family"];
+
+    pub fn get(&self, rank: &str) -> Option<&String> {
+        match rank {
+            "class" => self.class.as_ref(),
+            "order" => self.order.as_ref(),
+            "family" => self.family.as_ref(),
+            _ => None,
+        }
+    }
+
+    // (rank, name) from the top down, skipping the ones that aren't given
+    pub fn ranks(&self) -> Vec<(&'static str, &String)> {
+        Lineage::RANKS
+            .into_iter()
+            .filter_map(|rank| Some((rank, self.get(rank)?)))
+            .collect()
+    }
+}
+
+pub fn taxon_url(rank: &str, name: &str) -> String {
+    format!("{}/{}/{}", consts::TAXON_URL, rank, name)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum Rank {
+    Species,
+    Subspecies,
+    Variety,
+    Form,
+}
+
+impl Rank {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rank::Species => "species",
+            Rank::Subspecies => "subspecies",
+            Rank::Variety => "variety",
+            Rank::Form => "form",
+        }
+    }
+
+    // the connecting term in the name, which is also the url segment without the dot
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            Rank::Species => "",
+            Rank::Subspecies => "subsp.",
+            Rank::Variety => "var.",
+            Rank::Form => "f.",
+        }
+    }
+}
+
 #[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
 pub struct SpeInfo {
     pub name: String,
-    pub common_name: String,
+    pub rank: Rank,
+    pub author: Option<String>,
+    pub infraspecific: Vec<SpeInfo>,
+    pub common_name: Option<String>,
+    // `common_name: ''` rather than leaving the key out, only the linter cares
+    pub blank_common_name: bool,
     pub blurb: String,
+    pub edibility: Option<Edibility>,
+    pub season: Vec<Month>,
+    pub habitat: Option<String>,
+    pub substrate: Option<String>,
+    pub spore_print: Option<String>,
+    pub cap: Option<String>,
+    pub gills: Option<String>,
+    pub stem: Option<String>,
+    pub distribution: Option<String>,
+    pub references: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[cfg_attr(test, derive(Debug))]
+#[serde(rename_all = "lowercase")]
+pub enum Edibility {
+    Choice,
+    Edible,
+    Inedible,
+    Unknown,
+    Poisonous,
+    Deadly,
+}
+
+impl Edibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Edibility::Choice => "choice",
+            Edibility::Edible => "edible",
+            Edibility::Inedible => "inedible",
+            Edibility::Unknown => "unknown",
+            Edibility::Poisonous => "poisonous",
+            Edibility::Deadly => "deadly",
+        }
+    }
+
+    fn from_param(param: &str) -> Option<Edibility> {
+        [
+            Edibility::Choice,
+            Edibility::Edible,
+            Edibility::Inedible,
+            Edibility::Unknown,
+            Edibility::Poisonous,
+            Edibility::Deadly,
+        ]
+        .into_iter()
+        .find(|e| e.as_str() == param.to_lowercase())
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+// a month of the year, written in the yaml as 1-12 or a name like `oct`/`October`
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[cfg_attr(test, derive(Debug))]
+#[serde(try_from = "MonthDoc")]
+pub struct Month(u8);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MonthDoc {
+    Number(u8),
+    Name(String),
 }
 
-trait FillTemplate {
-    fn fill_menu(
-        &self,
-        categories: &Categories,
-        shroompedia: &Shroompedia,
-        html_frag: &str,
-    ) -> Content;
-    fn fill_cat(&self, cat: &CatInfo) -> Result<Content>;
-    fn fill_shroompage(&self, shroom: &ShroomInfo) -> Result<Content>;
-}
-
-impl FillTemplate for String {
-    fn fill_menu(
-        &self,
-        categories: &Categories,
-        shroompedia: &Shroompedia,
-        html_frag: &str,
-    ) -> Content {
-        self.replace("{MENU}", &html::menu(categories, html_frag))
-            .replace("{SEARCH}", &html::search_opts(shroompedia))
-            .replace("{DATA}", &html::search_obj(shroompedia))
-            .into_bytes()
+impl TryFrom<MonthDoc> for Month {
+    type Error = String;
+
+    fn try_from(month: MonthDoc) -> std::result::Result<Month, String> {
+        match month {
+            MonthDoc::Number(n) if (1..=12).contains(&n) => Ok(Month(n)),
+            MonthDoc::Number(n) => Err(format!("month `{}` is not between 1 and 12", n)),
+            MonthDoc::Name(name) => Month::from_name(&name)
+                .ok_or_else(|| format!("`{}` is not a month", name)),
+        }
+    }
+}
+
+impl Month {
+    pub fn name(&self) -> &'static str {
+        MONTHS[self.0 as usize - 1]
+    }
+
+    fn from_name(name: &str) -> Option<Month> {
+        let name = name.trim().to_lowercase();
+        MONTHS
+            .iter()
+            .position(|month| name.get(..3) == Some(month))
+            .map(|i| Month(i as u8 + 1))
     }
-    fn fill_cat(&self, cat: &CatInfo) -> Result<Content> {
-        Ok(self
-            .replace("{TITLE}", &cat.name)
-            .replace("{DATA}", &cat.htmlify()?)
-            .into_bytes())
+
+    fn from_param(param: &str) -> Option<Month> {
+        match param.parse::<u8>() {
+            Ok(n) => Month::try_from(MonthDoc::Number(n)).ok(),
+            Err(_) => Month::from_name(param),
+        }
     }
-    fn fill_shroompage(&self, shroom: &ShroomInfo) -> Result<Content> {
-        Ok(self
-            .replace(
-                "{TITLE}",
-                &format!(
-                    "{} - {}",
-                    &shroom.latin_name,
-                    if shroom.info.common_name == "''" {
-                        ""
-                    } else {
-                        &shroom.info.common_name
-                    }
-                ),
-            )
-            .replace("{BLURB}", &shroom.info.blurb)
-            .replace("{DATA}", &shroom.htmlify()?)
-            .into_bytes())
+}
+
+// `?edibility=edible&month=oct&habitat=beech&spore_print=white` on a category page
+#[derive(Default)]
+pub struct Filter {
+    pub edibility: Option<Edibility>,
+    pub month: Option<Month>,
+    pub habitat: Option<String>,
+    pub spore_print: Option<String>,
+}
+
+impl Filter {
+    pub fn from_query(query: &Query) -> Filter {
+        Filter {
+            edibility: query.get_param("edibility").and_then(Edibility::from_param),
+            month: query.get_param("month").and_then(Month::from_param),
+            habitat: query.get_param("habitat").map(str::to_lowercase),
+            spore_print: query.get_param("spore_print").map(str::to_lowercase),
+        }
+    }
+
+    pub fn matches(&self, species: &SpeInfo) -> bool {
+        let contains = |field: &Option<String>, wanted: &Option<String>| match wanted {
+            Some(wanted) => field
+                .as_ref()
+                .is_some_and(|field| field.to_lowercase().contains(wanted.as_str())),
+            None => true,
+        };
+        self.edibility.is_none_or(|e| species.edibility == Some(e))
+            && self.month.is_none_or(|m| species.season.contains(&m))
+            && (contains(&species.habitat, &self.habitat)
+                || contains(&species.substrate, &self.habitat))
+            && contains(&species.spore_print, &self.spore_print)
     }
 }
 
-pub async fn get(
-    (categories, shroompedia): &(Categories, Shroompedia),
-    request: &str,
-) -> Result<Response> {
+pub async fn get(mycology: &Mycology, request: &str) -> Result<Response> {
+    let (categories, shroompedia, index, images) = mycology;
     let mime_type = "text/html";
+    let (request, query) = request::split_query(request);
+    let request = request.as_str();
+    let segments = request
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<&str>>();
 
-    if request == "/" {
+    let page = |template, ctx| async move {
         Ok(Response {
             status: consts::status::HTTP_200,
             mime_type,
-            content: html::from_file(consts::PATH.menu)?.fill_menu(
-                categories,
-                shroompedia,
-                &html::from_file(consts::PATH.frag_menu)?,
-            ),
-        })
-    } else if let Some(shroom) = shroompedia.iter().find(|shroom| {
-        shroom.url == request
-    }) {
-        Ok(Response {
-            status: consts::status::HTTP_200,
-            mime_type,
-            content: html::from_file(consts::PATH.shroompage)?.fill_shroompage(shroom)?,
-        })
-    } else if let Some(cat) = categories
-        .iter()
-        .find(|cat| {
-            cat.name == request.replace("/", "")})
-    {
-        Ok(Response {
-            status: consts::status::HTTP_200,
-            mime_type,
-            content: html::from_file(consts::PATH.catpage)?.fill_cat(cat)?,
+            headers: vec![],
+            content: template::render(template, ctx).await?.into_bytes(),
         })
+    };
+
+    if request == "/" {
+        page(templates::MENU, html::menu(categories, shroompedia)).await
+    } else if request == consts::SEARCH_PATH {
+        search::get(shroompedia, index, &query).await
+    } else if let Some(shroom) = shroompedia.iter().find(|shroom| shroom.url == request) {
+        page(templates::SPECIES, shroom.context(categories, images)).await
+    } else if let [cat_name] = segments[..] {
+        match categories.iter().find(|cat| cat.name == cat_name) {
+            Some(cat) => {
+                page(
+                    templates::CATEGORY,
+                    cat.context(&Filter::from_query(&query), images),
+                )
+                .await
+            }
+            None => not_found(mycology, request).await,
+        }
+    } else if let [cat_name, gen_name] = segments[..] {
+        match categories
+            .iter()
+            .filter(|cat| cat.name == cat_name)
+            .find_map(|cat| Some((cat, cat.genera.iter().find(|gen| gen.name == gen_name)?)))
+        {
+            Some((cat, gen)) => page(templates::TAXON, gen.context(cat, images)).await,
+            None => not_found(mycology, request).await,
+        }
+    } else if let [taxon, rank, name] = segments[..] {
+        match html::taxon(categories, images, rank, name) {
+            Some(ctx) if format!("/{}", taxon) == consts::TAXON_URL => {
+                page(templates::TAXON, ctx).await
+            }
+            _ => not_found(mycology, request).await,
+        }
     } else {
-        Err(Box::new(io::Error::new(
-            io::ErrorKind::NotFound,
-            "Not Found",
-        )))
+        not_found(mycology, request).await
     }
 }
+
+// the usual 404 page, plus whatever the request was probably meant to be
+async fn not_found(mycology: &Mycology, request: &str) -> Result<Response> {
+    let suggestions = search::suggest(mycology, request)
+        .into_iter()
+        .map(|(name, url)| context! { name, url })
+        .collect::<Vec<_>>();
+    Ok(Response {
+        status: consts::status::HTTP_404,
+        mime_type: "text/html",
+        headers: vec![],
+        content: template::render(templates::NF404, context! { suggestions })
+            .await?
+            .into_bytes(),
+    })
+}
@@ -0,0 +1,278 @@
+use {
+    super::parse::Shroompedia,
+    crate::{
+        consts::{DECODABLE_EXTENSIONS, IMAGE_DIR, IMAGE_EXTENSIONS, VARIANT_DIR, VARIANT_WIDTHS},
+        types::{Images, Result},
+    },
+    image::{
+        codecs::{jpeg::JpegEncoder, webp::WebPEncoder},
+        imageops::FilterType,
+        DynamicImage,
+    },
+    serde::Deserialize,
+    std::{cmp::Ordering, collections::HashMap, io, iter::Peekable, path::Path, str::Chars},
+    tokio::fs,
+};
+
+// what a resized copy can be encoded as
+#[derive(Clone, Copy, PartialEq)]
+pub enum Encoding {
+    #[cfg(feature = "avif")]
+    Avif,
+    Webp,
+    Jpeg,
+}
+
+impl Encoding {
+    pub const ALL: &'static [Encoding] = &[
+        #[cfg(feature = "avif")]
+        Encoding::Avif,
+        Encoding::Webp,
+        Encoding::Jpeg,
+    ];
+
+    // what `thumbnails` makes and galleries offer, smallest first since browsers take
+    // the first they support; lossless webp comes out bigger than the jpeg, so it's
+    // only made for `/img` requests that ask for it
+    pub const VARIANTS: &'static [Encoding] = &[
+        #[cfg(feature = "avif")]
+        Encoding::Avif,
+        Encoding::Jpeg,
+    ];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "avif")]
+            Encoding::Avif => "avif",
+            Encoding::Webp => "webp",
+            Encoding::Jpeg => "jpg",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "avif")]
+            Encoding::Avif => "image/avif",
+            Encoding::Webp => "image/webp",
+            Encoding::Jpeg => "image/jpeg",
+        }
+    }
+
+    fn from_extension(extension: &str) -> Option<Encoding> {
+        Encoding::ALL
+            .iter()
+            .copied()
+            .find(|encoding| encoding.extension() == extension)
+    }
+
+    // `webp`, `jpg` or `jpeg`, and `avif` when built with it
+    pub fn from_param(param: &str) -> Option<Encoding> {
+        match param.to_lowercase().as_str() {
+            "jpeg" => Some(Encoding::Jpeg),
+            param => Encoding::from_extension(param),
+        }
+    }
+
+    // webp is lossless, the only kind the pure-rust encoder writes
+    pub fn encode(&self, image: &DynamicImage) -> Result<Vec<u8>> {
+        let mut out = vec![];
+        match self {
+            #[cfg(feature = "avif")]
+            Encoding::Avif => image.to_rgba8().write_with_encoder(
+                image::codecs::avif::AvifEncoder::new_with_speed_quality(&mut out, 8, 70),
+            )?,
+            Encoding::Webp => image
+                .to_rgba8()
+                .write_with_encoder(WebPEncoder::new_lossless(&mut out))?,
+            Encoding::Jpeg => image
+                .to_rgb8()
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut out, 82))?,
+        }
+        Ok(out)
+    }
+}
+
+pub struct Image {
+    // under IMAGE_DIR
+    pub path: String,
+    // (width, encoding) of the resized copies under VARIANT_DIR, narrowest first
+    pub variants: Vec<(u32, Encoding)>,
+    pub metadata: Metadata,
+}
+
+// from `{image}.yaml` next to the image, all of it optional
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Metadata {
+    pub alt: Option<String>,
+    pub caption: Option<String>,
+    pub credit: Option<String>,
+    pub licence: Option<String>,
+}
+
+// the sidecar of an image under IMAGE_DIR, None if it hasn't got one
+async fn metadata(path: &str) -> std::result::Result<Option<Metadata>, String> {
+    let sidecar = Path::new(IMAGE_DIR).join(format!("{}.yaml", path));
+    match fs::read_to_string(&sidecar).await {
+        Ok(text) => serde_yaml::from_str(&text)
+            .map(Some)
+            .map_err(|e| format!("{}: {}", sidecar.display(), e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("{}: {}", sidecar.display(), e)),
+    }
+}
+
+impl Image {
+    // `boletes/Boletus/edulis/1.jpg` at 320 wide is `boletes/Boletus/edulis/1.jpg.320.jpg`
+    pub fn variant(&self, width: u32, encoding: Encoding) -> String {
+        variant(&self.path, width, encoding)
+    }
+}
+
+pub fn variant(path: &str, width: u32, encoding: Encoding) -> String {
+    format!("{}.{}.{}", path, width, encoding.extension())
+}
+
+// the widths to make of an image, never wider than the original
+pub fn widths(original: u32) -> Vec<u32> {
+    let mut widths = VARIANT_WIDTHS
+        .iter()
+        .map(|width| (*width).min(original))
+        .collect::<Vec<u32>>();
+    widths.dedup();
+    widths
+}
+
+pub fn resize(image: &DynamicImage, width: u32) -> DynamicImage {
+    match image.width() > width {
+        true => image.resize(width, u32::MAX, FilterType::Lanczos3),
+        false => image.clone(),
+    }
+}
+
+// a file the gallery can show, going by its extension
+pub fn supported(file: &str) -> bool {
+    extension_in(file, &IMAGE_EXTENSIONS)
+}
+
+// a file there can be resized copies of
+pub fn decodable(file: &str) -> bool {
+    extension_in(file, &DECODABLE_EXTENSIONS)
+}
+
+fn extension_in(file: &str, extensions: &[&str]) -> bool {
+    Path::new(file)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.contains(&ext.to_lowercase().as_str()))
+}
+
+// the images in every species' (and infraspecific taxon's) directory and the resized
+// copies made of them, read once per reload; keyed by url. Also (url, error) for each
+// sidecar that doesn't parse, whose image goes without
+pub async fn build(shroompedia: &Shroompedia) -> (Images, Vec<(String, String)>) {
+    let mut images = HashMap::new();
+    let mut errors = vec![];
+    for shroom in shroompedia {
+        let dir = shroom.url.trim_start_matches('/');
+        let mut files = list(&Path::new(IMAGE_DIR).join(dir), supported).await;
+        files.sort_by(|a, b| natural(a, b));
+
+        // `{image}.{width}.{extension}`, see Image::variant
+        let mut variants: HashMap<&str, Vec<(u32, Encoding)>> = HashMap::new();
+        let copies = list(&Path::new(VARIANT_DIR).join(dir), |_| true).await;
+        for copy in &copies {
+            let mut parts = copy.rsplitn(3, '.');
+            let (Some(extension), Some(width), Some(file)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            if let (Some(encoding), Ok(width)) =
+                (Encoding::from_extension(extension), width.parse())
+            {
+                variants.entry(file).or_default().push((width, encoding));
+            }
+        }
+
+        let mut listed = Vec::with_capacity(files.len());
+        for file in &files {
+            let mut variants = variants.remove(file.as_str()).unwrap_or_default();
+            variants.sort_by_key(|(width, _)| *width);
+            let path = format!("{}/{}", dir, file);
+            let metadata = match metadata(&path).await {
+                Ok(metadata) => metadata.unwrap_or_default(),
+                Err(e) => {
+                    errors.push((shroom.url.clone(), e));
+                    Metadata::default()
+                }
+            };
+            listed.push(Image {
+                path,
+                variants,
+                metadata,
+            });
+        }
+        images.insert(shroom.url.clone(), listed);
+    }
+    (images, errors)
+}
+
+// the names of the files in a directory that pass `keep`, none if it isn't there
+async fn list(dir: &Path, keep: fn(&str) -> bool) -> Vec<String> {
+    let read = async {
+        let mut entries = fs::read_dir(dir).await?;
+        let mut files = vec![];
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type().await?.is_file() && keep(&name) {
+                files.push(name);
+            }
+        }
+        Ok::<_, io::Error>(files)
+    };
+    match read.await {
+        Ok(files) => files,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+        Err(e) => {
+            eprintln!("{} {}", e, dir.display());
+            vec![]
+        }
+    }
+}
+
+// `edulis2.jpg` before `edulis10.jpg`: runs of digits compare by their value
+pub fn natural(a: &str, b: &str) -> Ordering {
+    let number = |chars: &mut Peekable<Chars>| {
+        let mut digits = String::new();
+        while let Some(c) = chars.next_if(char::is_ascii_digit) {
+            digits.push(c);
+        }
+        digits
+    };
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let order = match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, y) = (number(&mut a), number(&mut b));
+                let (short_x, short_y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                short_x
+                    .len()
+                    .cmp(&short_y.len())
+                    .then_with(|| short_x.cmp(short_y))
+                    .then_with(|| x.len().cmp(&y.len()))
+            }
+            (Some(x), Some(y)) => {
+                a.next();
+                b.next();
+                x.to_ascii_lowercase().cmp(&y.to_ascii_lowercase())
+            }
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use {
+    super::images::{self, Encoding},
+    crate::{
+        consts::{img, rate, status, IMAGE_DIR},
+        server::{
+            limit,
+            request::{self, GetParam},
+            response::Response,
+        },
+        types::{IpAddr, Result},
+    },
+    std::{
+        io,
+        path::Path,
+        process,
+        sync::atomic::{AtomicU64, Ordering},
+        time::UNIX_EPOCH,
+    },
+    tokio::{fs, sync::Semaphore, task},
+};
+
+// resizing is all cpu, so only a few at once however many are asked for
+static RESIZING: Semaphore = Semaphore::const_new(img::CONCURRENT);
+
+// numbers the files being written, so two resizes of the same image don't share one
+static PARTIAL: AtomicU64 = AtomicU64::new(0);
+
+pub fn handles(path: &str) -> bool {
+    path.strip_prefix(img::PATH)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+// `/img/{path under IMAGE_DIR}?w=320&fmt=jpg`, in the whitelisted widths and formats
+// only; a resize that isn't cached yet costs the client a token
+pub async fn get(request: &str, ip: IpAddr) -> Result<Response> {
+    let (path, query) = request::split_query(request);
+    let path = path
+        .strip_prefix(img::PATH)
+        .unwrap_or_default()
+        .trim_start_matches('/')
+        .to_string();
+    if path.split('/').any(|segment| segment == "..") {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Permission denied",
+        )));
+    }
+    let width = query
+        .get_param("w")
+        .and_then(|w| w.parse::<u32>().ok())
+        .filter(|w| img::WIDTHS.contains(w));
+    let encoding = Encoding::from_param(query.get_param("fmt").unwrap_or("jpg"));
+    let (Some(width), Some(encoding)) = (width, encoding) else {
+        let formats = Encoding::ALL
+            .iter()
+            .map(|encoding| encoding.extension())
+            .collect::<Vec<&str>>();
+        return Ok(text(
+            status::HTTP_400,
+            format!(
+                "w must be one of {:?} and fmt one of {:?}",
+                img::WIDTHS,
+                formats
+            ),
+        ));
+    };
+
+    let source = Path::new(IMAGE_DIR).join(&path);
+    let meta = fs::metadata(&source).await?;
+    if !meta.is_file() || !images::decodable(&path) {
+        return Err(Box::new(io::Error::from(io::ErrorKind::NotFound)));
+    }
+    let modified = meta.modified()?.duration_since(UNIX_EPOCH)?.as_secs();
+    let cached = Path::new(img::CACHE_DIR).join(format!(
+        "{}.{}.{}.{}",
+        path,
+        modified,
+        width,
+        encoding.extension()
+    ));
+    if let Ok(content) = fs::read(&cached).await {
+        return Ok(image(encoding, content));
+    }
+
+    if !limit::allow(ip) {
+        let mut response = text(status::HTTP_429, "slow down".to_string());
+        response
+            .headers
+            .push(("Retry-After", rate::REFILL.as_secs().to_string()));
+        return Ok(response);
+    }
+    // the header says how big it'll be decoded, before any of it is
+    let (w, h) = {
+        let source = source.clone();
+        task::spawn_blocking(move || image::image_dimensions(source)).await??
+    };
+    if meta.len() > img::MAX_BYTES || w as u64 * h as u64 > img::MAX_PIXELS {
+        return Ok(text(status::HTTP_403, "image too large".to_string()));
+    }
+
+    let _permit = RESIZING.acquire().await?;
+    let content = task::spawn_blocking(move || {
+        encoding.encode(&images::resize(&image::open(&source)?, width))
+    })
+    .await??;
+    if let Err(e) = store(&cached, &content).await {
+        eprintln!("{} {} - not cached", e, cached.display());
+    }
+    Ok(image(encoding, content))
+}
+
+// written aside and renamed, so a reader never sees half an image; the same width and
+// format made from an older version of the original is deleted
+async fn store(cached: &Path, content: &[u8]) -> io::Result<()> {
+    let (Some(parent), Some(name)) = (cached.parent(), cached.file_name()) else {
+        return Err(io::Error::from(io::ErrorKind::InvalidInput));
+    };
+    let name = name.to_string_lossy();
+    fs::create_dir_all(parent).await?;
+    let partial = parent.join(format!(
+        ".{}.{}.{}.partial",
+        name,
+        process::id(),
+        PARTIAL.fetch_add(1, Ordering::Relaxed)
+    ));
+    if let Err(e) = fs::write(&partial, content).await {
+        let _ = fs::remove_file(&partial).await;
+        return Err(e);
+    }
+    fs::rename(&partial, cached).await?;
+
+    // `{file}.{modified}.{width}.{extension}`
+    let key = |name: &str| {
+        let mut parts = name.rsplitn(4, '.');
+        let (extension, width, modified, file) =
+            (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        modified
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| (file.to_string(), width.to_string(), extension.to_string()))
+    };
+    let Some(current) = key(&name) else {
+        return Ok(());
+    };
+    let mut entries = fs::read_dir(parent).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let other = entry.file_name().to_string_lossy().into_owned();
+        if other != name && key(&other).as_ref() == Some(&current) {
+            match fs::remove_file(entry.path()).await {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    eprintln!("{} {} - stale copy not deleted", e, entry.path().display())
+                }
+                _ => (),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn image(encoding: Encoding, content: Vec<u8>) -> Response {
+    Response {
+        status: status::HTTP_200,
+        mime_type: encoding.mime_type(),
+        headers: vec![("Cache-Control", "public, max-age=86400".to_string())],
+        content,
+    }
+}
+
+fn text(status: &'static str, message: String) -> Response {
+    Response {
+        status,
+        mime_type: "text/plain",
+        headers: vec![],
+        content: message.into_bytes(),
+    }
+}
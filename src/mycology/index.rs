@@ -0,0 +1,309 @@
+use {
+    super::parse::{ShroomInfo, Shroompedia},
+    rust_stemmers::{Algorithm, Stemmer},
+    std::{
+        collections::{BTreeMap, HashMap},
+        ops::Bound,
+    },
+};
+
+const STOPWORDS: [&str; 24] = [
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "is", "it", "its", "of",
+    "on", "or", "that", "the", "this", "to", "with", "was", "were",
+];
+
+// latin names are matched as written, the english is stemmed
+#[derive(Clone, Copy)]
+enum Field {
+    Name,
+    Common,
+    Text,
+}
+
+impl Field {
+    const ALL: [Field; 3] = [Field::Name, Field::Common, Field::Text];
+
+    fn weight(&self) -> f64 {
+        match self {
+            Field::Name => 3.0,
+            Field::Common => 2.0,
+            Field::Text => 1.0,
+        }
+    }
+
+    fn content(&self, shroom: &ShroomInfo) -> String {
+        let info = &shroom.info;
+        match self {
+            Field::Name => shroom.latin_name.clone(),
+            Field::Common => info.common_name.clone().unwrap_or_default(),
+            Field::Text => [
+                Some(&info.blurb),
+                info.habitat.as_ref(),
+                info.substrate.as_ref(),
+                info.spore_print.as_ref(),
+                info.cap.as_ref(),
+                info.gills.as_ref(),
+                info.stem.as_ref(),
+                info.distribution.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<&str>>()
+            .join(" "),
+        }
+    }
+}
+
+// term -> (species, positions of the term in the field)
+type Postings = BTreeMap<String, Vec<(usize, Vec<usize>)>>;
+
+#[derive(Default)]
+pub struct Index {
+    fields: [Postings; 3],
+    species: usize,
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+enum Term {
+    Word(String),
+    Prefix(String),
+    Phrase(Vec<String>),
+}
+
+impl Index {
+    // ids are positions in the Shroompedia it was built from
+    pub fn build(shroompedia: &Shroompedia) -> Index {
+        let stemmer = Stemmer::create(Algorithm::English);
+        let mut index = Index {
+            species: shroompedia.len(),
+            ..Index::default()
+        };
+        for (id, shroom) in shroompedia.iter().enumerate() {
+            for (field, postings) in Field::ALL.iter().zip(index.fields.iter_mut()) {
+                let mut positions: HashMap<String, Vec<usize>> = HashMap::new();
+                for (position, token) in tokens(&stemmer, *field, &field.content(shroom)) {
+                    positions.entry(token).or_default().push(position);
+                }
+                for (token, positions) in positions {
+                    postings.entry(token).or_default().push((id, positions));
+                }
+            }
+        }
+        index
+    }
+
+    // `boletus`, `bolet*` and `"penny bun"`, every match adding to a species' score;
+    // returns (id, score), best first
+    pub fn search(&self, query: &str) -> Vec<(usize, f64)> {
+        let stemmer = Stemmer::create(Algorithm::English);
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for term in terms(query) {
+            for (field, postings) in Field::ALL.iter().zip(self.fields.iter()) {
+                for (id, count, df) in self.matches(&stemmer, *field, postings, &term) {
+                    let idf = (1.0 + self.species as f64 / df as f64).ln();
+                    *scores.entry(id).or_default() +=
+                        field.weight() * (1.0 + (count as f64).ln()) * idf;
+                }
+            }
+        }
+        let mut scores = scores.into_iter().collect::<Vec<(usize, f64)>>();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scores
+    }
+
+    // (id, occurrences, species with any occurrence) for one term in one field
+    fn matches(
+        &self,
+        stemmer: &Stemmer,
+        field: Field,
+        postings: &Postings,
+        term: &Term,
+    ) -> Vec<(usize, usize, usize)> {
+        let with_df = |hits: Vec<(usize, usize)>| {
+            let df = hits.len();
+            hits.into_iter()
+                .map(|(id, count)| (id, count, df))
+                .collect()
+        };
+        match term {
+            Term::Word(word) => match normalise(stemmer, field, word) {
+                Some(token) => postings
+                    .get(&token)
+                    .map(|docs| {
+                        docs.iter()
+                            .map(|(id, pos)| (*id, pos.len(), docs.len()))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                None => vec![],
+            },
+            // stemming can change a word's end as well as shorten it (`penny` is `penni`),
+            // so the prefix is tried both as typed and stemmed
+            Term::Prefix(prefix) => {
+                let mut tokens = BTreeMap::new();
+                let stemmed = normalise(stemmer, field, prefix);
+                for prefix in [Some(prefix), stemmed.as_ref()].into_iter().flatten() {
+                    let range = postings
+                        .range::<str, _>((Bound::Included(prefix.as_str()), Bound::Unbounded));
+                    tokens
+                        .extend(range.take_while(|(token, _)| token.starts_with(prefix.as_str())));
+                }
+                let mut hits: HashMap<usize, usize> = HashMap::new();
+                for docs in tokens.into_values() {
+                    for (id, positions) in docs {
+                        *hits.entry(*id).or_default() += positions.len();
+                    }
+                }
+                with_df(hits.into_iter().collect())
+            }
+            Term::Phrase(words) => {
+                // offsets from the phrase itself, so skipped stopwords still take up a place
+                let tokens = words
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(offset, word)| Some((offset, normalise(stemmer, field, word)?)))
+                    .collect::<Vec<(usize, String)>>();
+                let Some(((first_offset, first), rest)) = tokens.split_first() else {
+                    return vec![];
+                };
+                let Some(docs) = postings.get(first) else {
+                    return vec![];
+                };
+                let hits = docs
+                    .iter()
+                    .filter_map(|(id, starts)| {
+                        let count = starts
+                            .iter()
+                            .filter(|start| {
+                                rest.iter().all(|(offset, token)| {
+                                    self.position(postings, token, *id).is_some_and(|pos| {
+                                        pos.contains(&(**start + offset - first_offset))
+                                    })
+                                })
+                            })
+                            .count();
+                        (count > 0).then_some((*id, count))
+                    })
+                    .collect();
+                with_df(hits)
+            }
+        }
+    }
+
+    fn position<'a>(
+        &self,
+        postings: &'a Postings,
+        token: &str,
+        id: usize,
+    ) -> Option<&'a Vec<usize>> {
+        postings
+            .get(token)?
+            .iter()
+            .find(|(doc, _)| *doc == id)
+            .map(|(_, positions)| positions)
+    }
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '-')
+        .map(|word| word.trim_matches('-').to_lowercase())
+        .filter(|word| !word.is_empty())
+}
+
+fn normalise(stemmer: &Stemmer, field: Field, word: &str) -> Option<String> {
+    match field {
+        Field::Name => Some(word.to_string()),
+        _ if STOPWORDS.contains(&word) => None,
+        _ => Some(stemmer.stem(word).into_owned()),
+    }
+}
+
+// positions count stopwords too, so a phrase can't jump over one
+fn tokens(stemmer: &Stemmer, field: Field, text: &str) -> Vec<(usize, String)> {
+    words(text)
+        .enumerate()
+        .filter_map(|(position, word)| Some((position, normalise(stemmer, field, &word)?)))
+        .collect()
+}
+
+fn terms(query: &str) -> Vec<Term> {
+    query
+        .split('"')
+        .enumerate()
+        .flat_map(|(i, part)| match i % 2 {
+            // inside quotes
+            1 => vec![Term::Phrase(words(part).collect())],
+            _ => part
+                .split_whitespace()
+                .flat_map(|word| match word.strip_suffix('*') {
+                    Some(prefix) => words(prefix).map(Term::Prefix).collect::<Vec<Term>>(),
+                    None => words(word).map(Term::Word).collect(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::mycology::parse};
+
+    const YAML: &str = "
+boletes:
+  title: Boletes
+  Boletus:
+    edulis:
+      common_name: Penny bun
+      blurb: Grows at the base of trees. One of the best edible mushrooms.
+    badius:
+      common_name: Bay bolete
+      blurb: A mushroom of conifer woods, its base bruising blue.
+";
+
+    fn search(query: &str) -> Vec<String> {
+        let (_, shroompedia) = parse::from_str("test.yaml", YAML).unwrap();
+        Index::build(&shroompedia)
+            .search(query)
+            .into_iter()
+            .map(|(id, _)| shroompedia[id].latin_name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn terms_split_words_prefixes_and_phrases() {
+        assert_eq!(
+            terms(r#"Boletus bolet* "penny bun" spore-print"#),
+            vec![
+                Term::Word("boletus".into()),
+                Term::Prefix("bolet".into()),
+                Term::Phrase(vec!["penny".into(), "bun".into()]),
+                Term::Word("spore-print".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn stopwords_keep_their_positions() {
+        let stemmer = Stemmer::create(Algorithm::English);
+        assert_eq!(
+            tokens(&stemmer, Field::Text, "the base of trees"),
+            vec![(1, "base".to_string()), (3, "tree".to_string())]
+        );
+    }
+
+    #[test]
+    fn phrases_match_across_stopwords_only_in_place() {
+        assert_eq!(search(r#""base of trees""#), ["Boletus edulis"]);
+        assert_eq!(search(r#""base trees""#), Vec::<String>::new());
+        assert_eq!(search(r#""penny bun""#), ["Boletus edulis"]);
+    }
+
+    #[test]
+    fn prefixes_match_stemmed_words() {
+        assert_eq!(search("penny*"), ["Boletus edulis"]);
+        assert_eq!(search("pen*"), ["Boletus edulis"]);
+        assert_eq!(search("mushrooms*").len(), 2);
+        assert_eq!(search("bolet*").len(), 2);
+    }
+}
@@ -0,0 +1,119 @@
+use {
+    super::generate::{self, taxon_url, Lineage},
+    crate::{
+        consts::{status, SEARCH_PATH},
+        server::{request, response::Response},
+        types::{Mycology, Pages},
+    },
+    flate2::{write::GzEncoder, Compression},
+    std::{
+        collections::{HashMap, HashSet},
+        io::Write,
+        sync::Arc,
+    },
+    tokio::{runtime::Handle, task},
+};
+
+// a generated page as it was rendered at the last reload, gzipped too when that's smaller
+pub struct Page {
+    mime_type: &'static str,
+    content: Vec<u8>,
+    gzip: Option<Vec<u8>>,
+}
+
+impl Page {
+    pub fn response(&self, accepts_gzip: bool) -> Response {
+        let (content, headers) = match (&self.gzip, accepts_gzip) {
+            (Some(gzip), true) => (
+                gzip.clone(),
+                vec![
+                    ("Content-Encoding", "gzip".to_string()),
+                    ("Vary", "Accept-Encoding".to_string()),
+                ],
+            ),
+            (Some(_), false) => (
+                self.content.clone(),
+                vec![("Vary", "Accept-Encoding".to_string())],
+            ),
+            (None, _) => (self.content.clone(), vec![]),
+        };
+        Response {
+            status: status::HTTP_200,
+            mime_type: self.mime_type,
+            headers,
+            content,
+        }
+    }
+}
+
+// only an exact url, a query string like `?edibility=` always goes to the generator
+pub fn lookup<'a>(pages: &'a Pages, request: &str) -> Option<&'a Page> {
+    let (path, query) = request::split_query(request);
+    match query.is_empty() {
+        true => pages.get(&path),
+        false => None,
+    }
+}
+
+// every url the mycology host answers without a query string; filtered category
+// pages, search results and the api are still generated per request
+pub fn urls((categories, shroompedia, _, _): &Mycology) -> Vec<String> {
+    let mut urls = vec!["/".to_string(), SEARCH_PATH.to_string()];
+    for cat in categories {
+        urls.push(format!("/{}", cat.name));
+        for gen in &cat.genera {
+            urls.push(format!("/{}/{}", cat.name, gen.name));
+            for rank in Lineage::RANKS {
+                if let Some(name) = gen.lineage.get(rank) {
+                    urls.push(taxon_url(rank, name));
+                }
+            }
+        }
+    }
+    urls.extend(shroompedia.iter().map(|shroom| shroom.url.clone()));
+    // a genus in two categories shares its taxon pages
+    let mut seen = HashSet::new();
+    urls.retain(|url| seen.insert(url.clone()));
+    urls
+}
+
+// a page that fails to render is left out, and so is generated per request instead.
+// Rendering and gzipping the whole site is all cpu, so it's done off the runtime
+pub async fn render(mycology: Arc<Mycology>) -> Pages {
+    let handle = Handle::current();
+    task::spawn_blocking(move || handle.block_on(render_all(&mycology)))
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("{} - no pages cached", e);
+            HashMap::new()
+        })
+}
+
+async fn render_all(mycology: &Mycology) -> Pages {
+    let mut pages = HashMap::new();
+    for url in urls(mycology) {
+        match generate::get(mycology, &url).await {
+            Ok(response) if response.status == status::HTTP_200 => {
+                let gzip =
+                    gzip(&response.content).filter(|gzip| gzip.len() < response.content.len());
+                pages.insert(
+                    url,
+                    Page {
+                        mime_type: response.mime_type,
+                        content: response.content,
+                        gzip,
+                    },
+                );
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("{} - {} is not cached", e, url),
+        }
+    }
+    pages
+}
+
+fn gzip(content: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder = GzEncoder::new(vec![], Compression::best());
+    encoder.write_all(content).ok()?;
+    encoder.finish().ok()
+}
@@ -1,25 +1,316 @@
 use {
     crate::{
-        consts::{self},
-        mycology::generate::{CatInfo, GenInfo, SpeInfo},
-        types::{Categories, YamlChunks, YamlLines, YamlString},
+        consts,
+        mycology::generate::{CatInfo, Edibility, GenInfo, Lineage, Month, Rank, SpeInfo},
+        types::Categories,
     },
-    std::fs,
+    serde::{
+        de::{self, MapAccess, Visitor},
+        Deserialize, Deserializer,
+    },
+    std::{
+        collections::HashMap,
+        error, fmt, io,
+        marker::PhantomData,
+        path::PathBuf,
+    },
+    tokio::fs,
 };
 
-enum Layer {
-    Category,
-    Genus,
-    Species,
+// a mapping that keeps document order and rejects repeated keys
+struct Ordered<T>(Vec<(String, T)>);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Ordered<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct OrderedVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for OrderedVisitor<T> {
+            type Value = Ordered<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a mapping")
+            }
+
+            fn visit_unit<E: de::Error>(self) -> std::result::Result<Self::Value, E> {
+                Ok(Ordered(vec![]))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let mut entries: Vec<(String, T)> = vec![];
+                while let Some(key) = map.next_key::<String>()? {
+                    if entries.iter().any(|(k, _)| *k == key) {
+                        return Err(de::Error::custom(format!("duplicate key `{}`", key)));
+                    }
+                    let value = map.next_value()?;
+                    entries.push((key, value));
+                }
+                Ok(Ordered(entries))
+            }
+        }
+
+        deserializer.deserialize_map(OrderedVisitor(PhantomData))
+    }
+}
+
+impl<T> Default for Ordered<T> {
+    fn default() -> Self {
+        Ordered(vec![])
+    }
+}
+
+type Document = Ordered<CategoryDoc>;
+
+// `title` sits alongside the genus keys, so this can't be a plain derive
+struct CategoryDoc {
+    title: String,
+    genera: Vec<(String, GenusDoc)>,
+}
+
+impl<'de> Deserialize<'de> for CategoryDoc {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct CategoryVisitor;
+
+        impl<'de> Visitor<'de> for CategoryVisitor {
+            type Value = CategoryDoc;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a category mapping with a `title` and genera")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let mut title = None;
+                let mut genera: Vec<(String, GenusDoc)> = vec![];
+                while let Some(key) = map.next_key::<String>()? {
+                    if key == "title" {
+                        if title.is_some() {
+                            return Err(de::Error::duplicate_field("title"));
+                        }
+                        title = Some(map.next_value()?);
+                    } else if genera.iter().any(|(k, _)| *k == key) {
+                        return Err(de::Error::custom(format!("duplicate genus `{}`", key)));
+                    } else {
+                        let genus = map.next_value()?;
+                        genera.push((key, genus));
+                    }
+                }
+                Ok(CategoryDoc {
+                    title: title.ok_or_else(|| de::Error::missing_field("title"))?,
+                    genera,
+                })
+            }
+        }
+
+        deserializer.deserialize_map(CategoryVisitor)
+    }
+}
+
+// like a category, the genus's own keys sit alongside the species
+struct GenusDoc {
+    author: Option<String>,
+    lineage: Lineage,
+    species: Vec<(String, SpeciesDoc)>,
+}
+
+impl<'de> Deserialize<'de> for GenusDoc {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct GenusVisitor;
+
+        impl<'de> Visitor<'de> for GenusVisitor {
+            type Value = GenusDoc;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a genus mapping of species")
+            }
+
+            fn visit_unit<E: de::Error>(self) -> std::result::Result<Self::Value, E> {
+                Ok(GenusDoc {
+                    author: None,
+                    lineage: Lineage::default(),
+                    species: vec![],
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let mut genus = self.visit_unit()?;
+                while let Some(key) = map.next_key::<String>()? {
+                    let field = match key.as_str() {
+                        "author" => &mut genus.author,
+                        "class" => &mut genus.lineage.class,
+                        "order" => &mut genus.lineage.order,
+                        "family" => &mut genus.lineage.family,
+                        _ if genus.species.iter().any(|(k, _)| *k == key) => {
+                            return Err(de::Error::custom(format!("duplicate species `{}`", key)));
+                        }
+                        _ => {
+                            let species = map.next_value()?;
+                            genus.species.push((key, species));
+                            continue;
+                        }
+                    };
+                    if field.is_some() {
+                        return Err(de::Error::custom(format!("duplicate field `{}`", key)));
+                    }
+                    *field = non_empty(map.next_value()?);
+                }
+                Ok(genus)
+            }
+        }
+
+        deserializer.deserialize_map(GenusVisitor)
+    }
+}
+
+// a species holds its subspecies, varieties and forms, which can't hold any of their own
+type SpeciesDoc = TaxonDoc<TaxonDoc<NoTaxa>>;
+
+enum NoTaxa {}
+
+impl<'de> Deserialize<'de> for NoTaxa {
+    fn deserialize<D: Deserializer<'de>>(_: D) -> std::result::Result<Self, D::Error> {
+        Err(de::Error::custom(
+            "subspecies, varieties and forms can't have any of their own",
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, bound = "I: Deserialize<'de>")]
+struct TaxonDoc<I> {
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    subspecies: Ordered<I>,
+    #[serde(default)]
+    varieties: Ordered<I>,
+    #[serde(default)]
+    forms: Ordered<I>,
+    #[serde(default)]
+    common_name: Option<String>,
+    #[serde(default)]
+    blurb: String,
+    #[serde(default)]
+    edibility: Option<Edibility>,
+    #[serde(default)]
+    season: Vec<Month>,
+    #[serde(default)]
+    habitat: Option<String>,
+    #[serde(default)]
+    substrate: Option<String>,
+    #[serde(default)]
+    spore_print: Option<String>,
+    #[serde(default)]
+    cap: Option<String>,
+    #[serde(default)]
+    gills: Option<String>,
+    #[serde(default)]
+    stem: Option<String>,
+    #[serde(default)]
+    distribution: Option<String>,
+    #[serde(default)]
+    references: Vec<String>,
+}
+
+trait IntoSpeInfo {
+    fn into_spe_info(self, name: String, rank: Rank) -> SpeInfo;
+}
+
+impl IntoSpeInfo for NoTaxa {
+    fn into_spe_info(self, _: String, _: Rank) -> SpeInfo {
+        match self {}
+    }
+}
+
+impl<I: IntoSpeInfo> IntoSpeInfo for TaxonDoc<I> {
+    fn into_spe_info(self, name: String, rank: Rank) -> SpeInfo {
+        SpeInfo {
+            name,
+            rank,
+            author: non_empty(self.author),
+            infraspecific: [
+                (Rank::Subspecies, self.subspecies),
+                (Rank::Variety, self.varieties),
+                (Rank::Form, self.forms),
+            ]
+            .into_iter()
+            .flat_map(|(rank, taxa)| {
+                taxa.0
+                    .into_iter()
+                    .map(move |(name, doc)| doc.into_spe_info(name, rank))
+            })
+            .collect(),
+            blank_common_name: self.common_name.as_ref().is_some_and(|v| v.trim().is_empty()),
+            common_name: non_empty(self.common_name),
+            blurb: self.blurb,
+            edibility: self.edibility,
+            season: self.season,
+            habitat: non_empty(self.habitat),
+            substrate: non_empty(self.substrate),
+            spore_print: non_empty(self.spore_print),
+            cap: non_empty(self.cap),
+            gills: non_empty(self.gills),
+            stem: non_empty(self.stem),
+            distribution: non_empty(self.distribution),
+            references: self.references,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct YamlError {
+    pub file: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
 }
 
-impl Layer {
-    fn condition(&self, s: &str) -> bool {
-        use Layer::*;
-        match self {
-            Category => !s.starts_with("  ") && s.ends_with(':'),
-            Genus => s.starts_with("  ") && !s.starts_with("   ") && s.ends_with(':'),
-            Species => s.starts_with("    ") && s.ends_with(':'),
+impl fmt::Display for YamlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(f, "{}:{}:{}: {}", self.file, line, column, self.message)
+            }
+            _ => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+impl error::Error for YamlError {}
+
+impl YamlError {
+    pub fn from_io(file: &str, e: io::Error) -> YamlError {
+        YamlError::new(file, e.to_string())
+    }
+
+    fn new(file: &str, message: String) -> YamlError {
+        YamlError {
+            file: file.to_string(),
+            line: None,
+            column: None,
+            message,
+        }
+    }
+
+    fn from_serde(file: &str, e: serde_yaml::Error) -> YamlError {
+        let location = e.location();
+        let message = e.to_string();
+        YamlError {
+            file: file.to_string(),
+            line: location.as_ref().map(|l| l.line()),
+            column: location.as_ref().map(|l| l.column()),
+            // serde_yaml appends the location to its message, it's in the fields already
+            message: match message.rsplit_once(" at line ") {
+                Some((message, _)) => message.to_string(),
+                None => message,
+            },
         }
     }
 }
@@ -28,63 +319,68 @@ trait Construct {
     fn construct(self) -> (Categories, Shroompedia);
 }
 
-impl Construct for YamlString {
+impl Construct for Document {
     fn construct(self) -> (Categories, Shroompedia) {
-        let lines: YamlLines = self.lines().map(str::to_string).collect();
-        let (cat_structs, shroompedia) = lines
-            .mangle(Layer::Category)
-            .iter()
-            .map(|cat_chunk| {
-                let mut iter = cat_chunk.iter();
-                let cat_name = iter.next().sanitise();
-                let menu_label = iter.next().sanitise();
-                let (gen_structs, shroompedia) = iter
-                    .map(String::to_owned)
-                    .collect::<YamlLines>()
-                    .mangle(Layer::Genus)
-                    .iter()
-                    .map(|gen_chunk| {
-                        let mut iter = gen_chunk.iter();
-                        let gen_name = iter.next().sanitise();
-                        let (spec_structs, shroompedia): (Vec<SpeInfo>, Shroompedia) = iter
-                            .map(String::to_owned)
-                            .collect::<YamlLines>()
-                            .mangle(Layer::Species)
-                            .iter()
-                            .map(|spe_chunk| {
-                                let mut iter = spe_chunk.iter();
-                                let spe_name = iter.next().sanitise();
-                                let common_name = iter.next().sanitise();
-                                let spe_blurb = iter.next().sanitise();
-                                let spe_info = SpeInfo {
-                                    name: spe_name.clone(),
-                                    common_name: common_name.clone(),
-                                    blurb: spe_blurb,
-                                };
+        let (cat_structs, shroompedia) = self
+            .0
+            .into_iter()
+            .map(|(cat_name, cat_doc)| {
+                let (gen_structs, shroompedia) = cat_doc
+                    .genera
+                    .into_iter()
+                    .map(|(gen_name, gen_doc)| {
+                        let (spec_structs, shroompedia): (Vec<SpeInfo>, Vec<Shroompedia>) = gen_doc
+                            .species
+                            .into_iter()
+                            .map(|(spe_name, spe_doc)| {
+                                let spe_info = spe_doc.into_spe_info(spe_name, Rank::Species);
                                 let shroom_info = ShroomInfo {
                                     cat: cat_name.clone(),
                                     gen: gen_name.clone(),
-                                    spe: spe_name.clone(),
-                                    latin_name: format!("{} {}", gen_name, spe_name),
+                                    spe: spe_info.name.clone(),
+                                    latin_name: format!("{} {}", gen_name, spe_info.name),
+                                    lineage: gen_doc.lineage.clone(),
                                     info: spe_info.clone(),
-                                    url: format!("/{}/{}/{}", cat_name, gen_name, spe_name),
+                                    url: format!("/{}/{}/{}", cat_name, gen_name, spe_info.name),
                                 };
-                                (spe_info, shroom_info)
+                                let infraspecific = spe_info
+                                    .infraspecific
+                                    .iter()
+                                    .map(|infra| ShroomInfo {
+                                        latin_name: format!(
+                                            "{} {} {}",
+                                            shroom_info.latin_name,
+                                            infra.rank.abbreviation(),
+                                            infra.name
+                                        ),
+                                        info: infra.clone(),
+                                        url: format!(
+                                            "{}/{}/{}",
+                                            shroom_info.url,
+                                            infra.rank.abbreviation().trim_end_matches('.'),
+                                            infra.name
+                                        ),
+                                        ..shroom_info.clone()
+                                    })
+                                    .collect::<Shroompedia>();
+                                (spe_info, [vec![shroom_info], infraspecific].concat())
                             })
-                            .collect::<(Vec<SpeInfo>, Shroompedia)>();
+                            .collect();
                         (
                             GenInfo {
                                 name: gen_name,
+                                author: gen_doc.author,
+                                lineage: gen_doc.lineage,
                                 species: spec_structs,
                             },
-                            shroompedia,
+                            shroompedia.into_iter().flatten().collect::<Shroompedia>(),
                         )
                     })
                     .collect::<(Vec<GenInfo>, Vec<Shroompedia>)>();
                 (
                     CatInfo {
                         name: cat_name,
-                        menu_label,
+                        menu_label: cat_doc.title,
                         genera: gen_structs,
                     },
                     shroompedia.into_iter().flatten().collect(),
@@ -95,68 +391,481 @@ impl Construct for YamlString {
     }
 }
 
-trait Sanitise {
-    fn sanitise(self) -> String;
+fn non_empty(field: Option<String>) -> Option<String> {
+    field.filter(|v| !v.trim().is_empty())
+}
+
+pub type Shroompedia = Vec<ShroomInfo>;
+#[derive(Clone)]
+pub struct ShroomInfo {
+    pub cat: String,
+    pub gen: String,
+    pub spe: String,
+    pub latin_name: String,
+    pub lineage: Lineage,
+    pub info: SpeInfo,
+    pub url: String,
+}
+
+// a whole document as yaml text, which takes in json too
+pub fn from_str(
+    file: &str,
+    yaml_string: &str,
+) -> std::result::Result<(Categories, Shroompedia), YamlError> {
+    let mut sources = Sources::default();
+    for (name, doc) in parse::<Document>(file, yaml_string)?.0 {
+        sources.category(file, name, doc)?;
+    }
+    Ok(sources.document.construct())
 }
 
-impl Sanitise for Option<&String> {
-    fn sanitise(self) -> String {
-        self.unwrap_or(&String::new())
-            .trim()
-            .trim_start_matches("blurb: ")
-            .trim_start_matches("title: ")
-            .trim_start_matches("common_name: ")
-            .trim_start_matches("name: ")
-            .replace(':', "")
+// csv records keyed by column, see CSV_TAXON_COLUMNS and CSV_DETAIL_COLUMNS
+pub fn from_rows(
+    file: &str,
+    rows: Vec<HashMap<String, String>>,
+) -> std::result::Result<(Categories, Shroompedia), YamlError> {
+    let mut sources = Sources::default();
+    for (n, row) in rows.into_iter().enumerate() {
+        // the header is row 1
+        let origin = format!("{} row {}", file, n + 2);
+        let cell = |column: &str| row.get(column).map(|v| v.trim()).unwrap_or_default();
+        let required = |column: &str| match cell(column) {
+            "" => Err(YamlError::new(&origin, format!("missing `{}`", column))),
+            v => Ok(v.to_string()),
+        };
+        if let Some(column) = row.keys().find(|column| {
+            !consts::CSV_TAXON_COLUMNS.contains(&column.as_str())
+                && !consts::CSV_DETAIL_COLUMNS.contains(&column.as_str())
+        }) {
+            return Err(YamlError::new(&origin, format!("unknown column `{}`", column)));
+        }
+
+        // a row without a species only defines its category, and genus if it has one
+        let cat = required("category")?;
+        if !sources.origins.contains_key(&cat) {
+            let title = required("title")?;
+            sources.category(&origin, cat.clone(), CategoryDoc { title, genera: vec![] })?;
+        }
+        let gen = match (cell("genus"), cell("species")) {
+            ("", "") => continue,
+            _ => required("genus")?,
+        };
+        if !sources.origins.contains_key(&format!("{}/{}", cat, gen)) {
+            let genus = GenusDoc {
+                author: non_empty(Some(cell("genus_author").to_string())),
+                lineage: Lineage {
+                    class: non_empty(Some(cell("class").to_string())),
+                    order: non_empty(Some(cell("order").to_string())),
+                    family: non_empty(Some(cell("family").to_string())),
+                },
+                species: vec![],
+            };
+            sources.genus(&origin, &cat, gen.clone(), genus)?;
+        }
+
+        if cell("species").is_empty() {
+            continue;
+        }
+        let spe = required("species")?;
+
+        let mut details = serde_yaml::Mapping::new();
+        for column in consts::CSV_DETAIL_COLUMNS {
+            let value = match (column, cell(column)) {
+                (_, "") => continue,
+                ("common_name", "''") => "".into(),
+                ("season", v) => v.split(',').map(|m| serde_yaml::Value::from(m.trim())).collect(),
+                ("references", v) => v.lines().map(|r| serde_yaml::Value::from(r.trim())).collect(),
+                (_, v) => v.into(),
+            };
+            details.insert(column.into(), value);
+        }
+        let details = serde_yaml::Value::Mapping(details);
+        let invalid = |e: serde_yaml::Error| YamlError::new(&origin, e.to_string());
+
+        match cell("rank") {
+            "" | "species" => {
+                let doc = SpeciesDoc::deserialize(details).map_err(invalid)?;
+                sources.species(&origin, &cat, &gen, spe, doc)?
+            }
+            rank => {
+                let name = required("name")?;
+                let doc = TaxonDoc::<NoTaxa>::deserialize(details).map_err(invalid)?;
+                sources.infraspecific(&origin, [&cat, &gen, &spe], rank, name, doc)?
+            }
+        }
     }
+    Ok(sources.document.construct())
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(
+    file: &str,
+    yaml_string: &str,
+) -> std::result::Result<T, YamlError> {
+    serde_yaml::from_str(yaml_string).map_err(|e| YamlError::from_serde(file, e))
 }
 
-trait Mangle {
-    fn mangle(self, layer: Layer) -> YamlChunks;
+// YAML_FILE and the DATA_DIR files merged into one document, remembering which file
+// each category, genus and species came from so duplicates can name both
+#[derive(Default)]
+struct Sources {
+    document: Document,
+    origins: HashMap<String, String>,
 }
 
-impl Mangle for YamlLines {
-    fn mangle(self, layer: Layer) -> YamlChunks {
-        let divisions: Vec<usize> = self
-            .iter()
-            .enumerate()
-            .filter_map(|(i, s)| layer.condition(s).then_some(i))
-            .collect();
-        let m_divs = &divisions;
+impl Sources {
+    fn claim(&mut self, key: String, file: &str) -> std::result::Result<(), YamlError> {
+        match self.origins.get(&key) {
+            Some(other) => Err(YamlError::new(
+                file,
+                format!("`{}` is already defined in {}", key, other),
+            )),
+            None => {
+                self.origins.insert(key, file.to_string());
+                Ok(())
+            }
+        }
+    }
 
-        divisions
-            .iter()
-            .enumerate()
-            .map(|(i, n)| {
-                self[if let Some(v) = m_divs.get(i + 1) {
-                    *n..*v
-                } else {
-                    *n..self.len()
-                }]
-                .to_vec()
-            })
-            .collect()
+    fn category(
+        &mut self,
+        file: &str,
+        name: String,
+        doc: CategoryDoc,
+    ) -> std::result::Result<(), YamlError> {
+        self.claim(name.clone(), file)?;
+        self.document.0.push((
+            name.clone(),
+            CategoryDoc {
+                title: doc.title,
+                genera: vec![],
+            },
+        ));
+        for (gen_name, gen_doc) in doc.genera {
+            self.genus(file, &name, gen_name, gen_doc)?;
+        }
+        Ok(())
+    }
+
+    fn genus(
+        &mut self,
+        file: &str,
+        cat_name: &str,
+        name: String,
+        doc: GenusDoc,
+    ) -> std::result::Result<(), YamlError> {
+        self.claim(format!("{}/{}", cat_name, name), file)?;
+        let Some((_, cat_doc)) = self.document.0.iter_mut().find(|(k, _)| k == cat_name) else {
+            return Err(YamlError::new(
+                file,
+                format!(
+                    "category `{}` has no `{}.yaml` giving its title",
+                    cat_name, cat_name
+                ),
+            ));
+        };
+        let species = doc.species;
+        cat_doc.genera.push((
+            name.clone(),
+            GenusDoc {
+                species: vec![],
+                ..doc
+            },
+        ));
+        for (spe_name, spe_doc) in species {
+            self.species(file, cat_name, &name, spe_name, spe_doc)?;
+        }
+        Ok(())
+    }
+
+    fn species(
+        &mut self,
+        file: &str,
+        cat_name: &str,
+        gen_name: &str,
+        name: String,
+        doc: SpeciesDoc,
+    ) -> std::result::Result<(), YamlError> {
+        self.claim(format!("{}/{}/{}", cat_name, gen_name, name), file)?;
+        let Some((_, cat_doc)) = self.document.0.iter_mut().find(|(k, _)| k == cat_name) else {
+            return Err(YamlError::new(
+                file,
+                format!(
+                    "category `{}` has no `{}.yaml` giving its title",
+                    cat_name, cat_name
+                ),
+            ));
+        };
+        // a species file may stand in a genus directory without a genus file of its own
+        let genus = match cat_doc.genera.iter().position(|(k, _)| k == gen_name) {
+            Some(i) => &mut cat_doc.genera[i].1,
+            None => {
+                cat_doc.genera.push((
+                    gen_name.to_string(),
+                    GenusDoc {
+                        author: None,
+                        lineage: Lineage::default(),
+                        species: vec![],
+                    },
+                ));
+                &mut cat_doc.genera.last_mut().unwrap().1
+            }
+        };
+        genus.species.push((name, doc));
+        Ok(())
+    }
+
+    fn infraspecific(
+        &mut self,
+        file: &str,
+        [cat_name, gen_name, spe_name]: [&str; 3],
+        rank: &str,
+        name: String,
+        doc: TaxonDoc<NoTaxa>,
+    ) -> std::result::Result<(), YamlError> {
+        let species = self
+            .document
+            .0
+            .iter_mut()
+            .filter(|(k, _)| k == cat_name)
+            .flat_map(|(_, cat_doc)| cat_doc.genera.iter_mut())
+            .filter(|(k, _)| k == gen_name)
+            .flat_map(|(_, gen_doc)| gen_doc.species.iter_mut())
+            .find(|(k, _)| k == spe_name)
+            .map(|(_, spe_doc)| spe_doc)
+            .ok_or_else(|| {
+                YamlError::new(
+                    file,
+                    format!("species `{} {}` isn't defined before its {}", gen_name, spe_name, rank),
+                )
+            })?;
+        let (taxa, segment) = match rank {
+            "subspecies" => (&mut species.subspecies, "subsp"),
+            "variety" => (&mut species.varieties, "var"),
+            "form" => (&mut species.forms, "f"),
+            _ => {
+                return Err(YamlError::new(
+                    file,
+                    format!("rank `{}` isn't species, subspecies, variety or form", rank),
+                ))
+            }
+        };
+        let key = format!("{}/{}/{}/{}/{}", cat_name, gen_name, spe_name, segment, name);
+        if let Some(other) = self.origins.get(&key) {
+            return Err(YamlError::new(
+                file,
+                format!("`{}` is already defined in {}", key, other),
+            ));
+        }
+        self.origins.insert(key, file.to_string());
+        taxa.0.push((name, doc));
+        Ok(())
     }
 }
 
-pub type Shroompedia = Vec<ShroomInfo>;
-pub struct ShroomInfo {
-    pub cat: String,
-    pub gen: String,
-    pub spe: String,
-    pub latin_name: String,
-    pub info: SpeInfo,
-    pub url: String,
+// every yaml file under the data dir as (file, [category, genus?, species?])
+async fn data_files() -> std::result::Result<Vec<(String, Vec<String>)>, YamlError> {
+    let root = PathBuf::from(consts::DATA_DIR);
+    let mut files = vec![];
+    let mut dirs = vec![root.clone()];
+    while let Some(dir) = dirs.pop() {
+        let dir_name = dir.to_string_lossy();
+        let mut entries = fs::read_dir(&dir)
+            .await
+            .map_err(|e| YamlError::from_io(&dir_name, e))?;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| YamlError::from_io(&dir_name, e))?
+        {
+            let path = entry.path();
+            let is_dir = entry
+                .file_type()
+                .await
+                .map_err(|e| YamlError::from_io(&path.to_string_lossy(), e))?
+                .is_dir();
+            let is_yaml = matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("yaml" | "yml")
+            );
+            if is_dir {
+                dirs.push(path);
+            } else if is_yaml {
+                let keys = path
+                    .strip_prefix(&root)
+                    .unwrap_or(&path)
+                    .with_extension("")
+                    .iter()
+                    .map(|key| key.to_string_lossy().into_owned())
+                    .collect();
+                files.push((path.to_string_lossy().into_owned(), keys));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+pub async fn yaml() -> std::result::Result<(Categories, Shroompedia), YamlError> {
+    let mut sources = Sources::default();
+    let has_data_dir = fs::metadata(consts::DATA_DIR)
+        .await
+        .is_ok_and(|meta| meta.is_dir());
+
+    match fs::read_to_string(consts::YAML_FILE).await {
+        Ok(yaml_string) => {
+            for (name, doc) in parse::<Document>(consts::YAML_FILE, &yaml_string)?.0 {
+                sources.category(consts::YAML_FILE, name, doc)?;
+            }
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound && has_data_dir => (),
+        Err(e) => return Err(YamlError::from_io(consts::YAML_FILE, e)),
+    }
+
+    if has_data_dir {
+        let mut files = data_files().await?;
+        // categories first, then genera, then species, so each has somewhere to go
+        files.sort_by_key(|(_, keys)| keys.len());
+        for (file, keys) in files {
+            let yaml_string = fs::read_to_string(&file)
+                .await
+                .map_err(|e| YamlError::from_io(&file, e))?;
+            match &keys[..] {
+                [cat] => sources.category(&file, cat.clone(), parse(&file, &yaml_string)?)?,
+                [cat, gen] => {
+                    sources.genus(&file, cat, gen.clone(), parse(&file, &yaml_string)?)?
+                }
+                [cat, gen, spe] => {
+                    sources.species(&file, cat, gen, spe.clone(), parse(&file, &yaml_string)?)?
+                }
+                _ => {
+                    return Err(YamlError::new(
+                        &file,
+                        "too deep, expected <category>.yaml, <category>/<genus>.yaml \
+                         or <category>/<genus>/<species>.yaml"
+                            .to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(sources.document.construct())
 }
 
-pub async fn yaml() -> (Categories, Shroompedia) {
-    if let Ok(yaml_string) = fs::read_to_string(consts::YAML_FILE) {
-        let (cats,shroompediaa) = yaml_string.construct();
-        (cats,shroompediaa)
-    } else {
-        panic!(
-            "yaml munching error. :(\n Does the file exist and have correct permissions? - {}",
-            consts::YAML_FILE
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(yaml: &str) -> Categories {
+        from_str("test.yaml", yaml).unwrap().0
+    }
+
+    fn error(yaml: &str) -> YamlError {
+        from_str("test.yaml", yaml).err().unwrap()
+    }
+
+    #[test]
+    fn colons_and_block_scalars_in_text() {
+        let categories = parse(
+            r#"
+boletes:
+  title: "Boletes: the spongy ones"
+  Boletus:
+    edulis:
+      blurb: "Cap: brown, stem: white"
+      habitat: |
+        Beech and oak.
+        Also pine: sometimes.
+      cap: >
+        Brown and
+        greasy when wet.
+"#,
+        );
+        let edulis = &categories[0].genera[0].species[0];
+        assert_eq!(categories[0].menu_label, "Boletes: the spongy ones");
+        assert_eq!(edulis.blurb, "Cap: brown, stem: white");
+        assert_eq!(
+            edulis.habitat.as_deref(),
+            Some("Beech and oak.\nAlso pine: sometimes.\n")
+        );
+        assert_eq!(edulis.cap.as_deref(), Some("Brown and greasy when wet.\n"));
+    }
+
+    #[test]
+    fn keys_in_any_order_keep_document_order() {
+        let categories = parse(
+            "
+boletes:
+  Suillus:
+    luteus: {}
+    family: Suillaceae
+  Boletus:
+    edulis:
+      blurb: Penny bun
+      author: Bull.
+    author: Fr.
+  title: Boletes
+fungi:
+  title: Gilled fungi
+",
+        );
+        let names = |cat: &CatInfo| {
+            cat.genera
+                .iter()
+                .map(|g| g.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(categories[0].name, "boletes");
+        assert_eq!(categories[0].menu_label, "Boletes");
+        assert_eq!(names(&categories[0]), ["Suillus", "Boletus"]);
+        assert_eq!(
+            categories[0].genera[0].lineage.family.as_deref(),
+            Some("Suillaceae")
         );
+        assert_eq!(categories[0].genera[1].author.as_deref(), Some("Fr."));
+        assert_eq!(
+            categories[0].genera[1].species[0].author.as_deref(),
+            Some("Bull.")
+        );
+        assert_eq!(categories[1].name, "fungi");
+    }
+
+    #[test]
+    fn duplicate_keys_are_errors() {
+        for (yaml, message) in [
+            ("a:\n  title: A\na:\n  title: B\n", "`a`"),
+            ("a:\n  title: A\n  title: B\n", "title"),
+            ("a:\n  title: A\n  G:\n    s: {}\n  G:\n    t: {}\n", "G"),
+            ("a:\n  title: A\n  G:\n    s: {}\n    s: {}\n", "s"),
+            (
+                "a:\n  title: A\n  G:\n    family: F\n    family: F\n",
+                "family",
+            ),
+        ] {
+            let e = error(yaml);
+            assert!(e.message.contains("duplicate"), "{}", e);
+            assert!(e.message.contains(message), "{}", e);
+        }
+    }
+
+    #[test]
+    fn unknown_fields_are_errors() {
+        let e = error("a:\n  title: A\n  G:\n    s:\n      colour: brown\n");
+        assert!(e.message.contains("unknown field `colour`"), "{}", e);
+        let e = error("a:\n  G: {}\n");
+        assert!(e.message.contains("missing field `title`"), "{}", e);
+    }
+
+    #[test]
+    fn errors_point_at_the_line_and_column() {
+        let e = error("a:\n  title: A\n  G:\n    s:\n      blurb: fine\n      edibility: tasty\n");
+        assert_eq!((e.line, e.column), (Some(6), Some(18)), "{}", e);
+        assert!(e.message.contains("unknown variant `tasty`"), "{}", e);
+        assert!(!e.message.contains(" at line "), "{}", e);
+        assert!(e.to_string().starts_with("test.yaml:6:18: "), "{}", e);
+
+        let e = error("a:\n  title: [unclosed\n");
+        assert_eq!(e.file, "test.yaml");
+        assert!(e.line.is_some(), "{}", e);
     }
 }
@@ -0,0 +1,251 @@
+use {
+    super::{
+        index::Index,
+        parse::{ShroomInfo, Shroompedia},
+    },
+    crate::{
+        consts::{status, templates, SEARCH_LIMIT},
+        server::{request::GetParam, response::Response},
+        template,
+        types::{Mycology, Query, Result},
+    },
+    minijinja::context,
+    serde_json::json,
+    std::{cmp::Ordering, collections::HashMap, slice},
+    strsim::{jaro_winkler, levenshtein},
+};
+
+// below this a fuzzy name match is just noise
+const FUZZY_THRESHOLD: f64 = 0.85;
+
+// the share of a path's characters a typo can change and still be suggested
+const SUGGEST_THRESHOLD: f64 = 0.34;
+
+const SUGGESTIONS: usize = 5;
+
+pub struct Hit<'a> {
+    pub shroom: &'a ShroomInfo,
+    pub score: f64,
+}
+
+// fuzzy on the names, so typos still land, plus the index's relevance scaled to at most 0.5
+pub fn search<'a>(shroompedia: &'a Shroompedia, index: &Index, query: &str) -> Vec<Hit<'a>> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return vec![];
+    }
+    let words = query
+        .split(|c: char| c.is_whitespace() || c == '"' || c == '*')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>();
+    if words.is_empty() {
+        return vec![];
+    }
+
+    let relevance = index.search(&query);
+    let best = relevance.first().map(|(_, score)| *score).unwrap_or(1.0);
+    let relevance = relevance.into_iter().collect::<HashMap<usize, f64>>();
+
+    let mut hits = shroompedia
+        .iter()
+        .enumerate()
+        .filter_map(|(id, shroom)| {
+            let names = [Some(&shroom.latin_name), shroom.info.common_name.as_ref()];
+            let name = names
+                .into_iter()
+                .flatten()
+                .map(|name| name_score(&query, &words, &name.to_lowercase()))
+                .fold(0.0, f64::max);
+            let text = relevance.get(&id).map_or(0.0, |score| score / best * 0.5);
+            let score = name + text;
+            (score > 0.0).then_some(Hit { shroom, score })
+        })
+        .collect::<Vec<Hit>>();
+    hits.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.shroom.latin_name.cmp(&b.shroom.latin_name))
+    });
+    hits
+}
+
+fn name_score(query: &str, words: &[&str], name: &str) -> f64 {
+    if name == query {
+        return 1.0;
+    } else if name.starts_with(query) {
+        return 0.9;
+    } else if name.contains(query) {
+        return 0.75;
+    }
+    // each word of the query against its closest word of the name, so typos and
+    // either half of a binomial still land
+    let name_words = name.split_whitespace().collect::<Vec<&str>>();
+    let similarity = words
+        .iter()
+        .map(|word| {
+            name_words
+                .iter()
+                .map(|name_word| jaro_winkler(word, name_word))
+                .fold(jaro_winkler(word, name), f64::max)
+        })
+        .sum::<f64>()
+        / words.len() as f64;
+    match similarity >= FUZZY_THRESHOLD {
+        true => similarity * 0.7,
+        false => 0.0,
+    }
+}
+
+// (name, url) of the pages closest to a path that didn't match, by edit distance
+// over its segments, falling back to the index for paths that aren't near any url
+pub fn suggest(
+    (categories, shroompedia, index, _): &Mycology,
+    request: &str,
+) -> Vec<(String, String)> {
+    let wanted = segments(request);
+    if wanted.is_empty() {
+        return vec![];
+    }
+
+    let candidates = categories
+        .iter()
+        .flat_map(|cat| {
+            let cat_url = format!("/{}", cat.name);
+            let genera = cat
+                .genera
+                .iter()
+                .map(|gen| (gen.name.clone(), format!("{}/{}", cat_url, gen.name)))
+                .collect::<Vec<_>>();
+            [(cat.menu_label.clone(), cat_url)]
+                .into_iter()
+                .chain(genera)
+        })
+        .chain(
+            shroompedia
+                .iter()
+                .map(|shroom| (shroom.latin_name.clone(), shroom.url.clone())),
+        );
+    let mut scored = candidates
+        .filter_map(|(name, url)| {
+            let distance = distance(&wanted, &segments(&url));
+            (distance <= SUGGEST_THRESHOLD).then_some((distance, name, url))
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.2.cmp(&b.2)));
+
+    match scored.is_empty() {
+        false => scored
+            .into_iter()
+            .take(SUGGESTIONS)
+            .map(|(_, name, url)| (name, url))
+            .collect(),
+        true => index
+            .search(&wanted.join(" "))
+            .into_iter()
+            .take(SUGGESTIONS)
+            .map(|(id, _)| {
+                (
+                    shroompedia[id].latin_name.clone(),
+                    shroompedia[id].url.clone(),
+                )
+            })
+            .collect(),
+    }
+}
+
+fn segments(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.to_lowercase())
+        .collect()
+}
+
+// segment by segment, a missing or extra one counting as wholly different; a lone
+// misspelt name is also tried against just the last segment, at a small cost
+fn distance(wanted: &[String], candidate: &[String]) -> f64 {
+    let relative = |wanted: &[String], candidate: &[String]| {
+        let longest = wanted.len().max(candidate.len());
+        let (edits, length) = (0..longest).fold((0, 0), |(edits, length), i| {
+            match (wanted.get(i), candidate.get(i)) {
+                (Some(a), Some(b)) => (
+                    edits + levenshtein(a, b),
+                    length + a.chars().count().max(b.chars().count()),
+                ),
+                (Some(a), None) | (None, Some(a)) => {
+                    (edits + a.chars().count(), length + a.chars().count())
+                }
+                (None, None) => (edits, length),
+            }
+        });
+        edits as f64 / length.max(1) as f64
+    };
+    let whole = relative(wanted, candidate);
+    match (wanted, candidate.last()) {
+        ([name], Some(last)) if candidate.len() > 1 => {
+            whole.min(relative(slice::from_ref(name), slice::from_ref(last)) + 0.1)
+        }
+        _ => whole,
+    }
+}
+
+pub async fn get(shroompedia: &Shroompedia, index: &Index, query: &Query) -> Result<Response> {
+    let q = query.get_param("q").unwrap_or_default();
+    let hits = search(shroompedia, index, q);
+
+    if query.get_param("format") == Some("json") {
+        let limit = query
+            .get_param("limit")
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(SEARCH_LIMIT)
+            .min(SEARCH_LIMIT * 5);
+        let results = hits
+            .iter()
+            .take(limit)
+            .map(|hit| {
+                json!({
+                    "latin_name": hit.shroom.latin_name,
+                    "common_name": hit.shroom.info.common_name,
+                    "url": hit.shroom.url,
+                    "score": hit.score,
+                })
+            })
+            .collect::<Vec<_>>();
+        return Ok(Response {
+            status: status::HTTP_200,
+            mime_type: "application/json",
+            headers: vec![],
+            content: json!({ "query": q, "results": results })
+                .to_string()
+                .into_bytes(),
+        });
+    }
+
+    let results = hits
+        .iter()
+        .map(|hit| {
+            context! {
+                latin_name => hit.shroom.latin_name,
+                common_name => hit.shroom.info.common_name,
+                blurb => hit.shroom.info.blurb,
+                edibility => hit.shroom.info.edibility.map(|e| e.as_str()),
+                url => hit.shroom.url,
+            }
+        })
+        .collect::<Vec<_>>();
+    Ok(Response {
+        status: status::HTTP_200,
+        mime_type: "text/html",
+        headers: vec![],
+        content: template::render(
+            templates::SEARCH,
+            context! {
+                q,
+                results,
+                breadcrumbs => vec![context! { name => "Home", url => "/" }],
+            },
+        )
+        .await?
+        .into_bytes(),
+    })
+}
@@ -0,0 +1,154 @@
+use {
+    crate::{
+        consts::{status, templates, MYCOLOGY_ROOT, SEARCH_PATH},
+        mycology::{generate, images, index::Index, pages, parse},
+        server::request,
+        template,
+        types::Result,
+    },
+    minijinja::context,
+    std::{
+        collections::{BTreeSet, HashSet},
+        path::Path,
+        process::ExitCode,
+    },
+    tokio::fs,
+};
+
+// the attributes whose root-relative urls get rewritten
+const ATTRIBUTES: [&str; 3] = [" href=\"", " src=\"", " srcset=\""];
+
+// every prerendered page as `<dir>/<url>/index.html`, with links made relative and
+// the images and stylesheets they use copied alongside; search and the category
+// filters need the server, so their forms are left out
+pub async fn run(dir: &str) -> Result<ExitCode> {
+    template::static_export();
+    let (categories, shroompedia) = match parse::yaml().await {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return Ok(ExitCode::from(2));
+        }
+    };
+    let index = Index::build(&shroompedia);
+    let (images, errors) = images::build(&shroompedia).await;
+    for (_, e) in errors {
+        eprintln!("warning: {}", e);
+    }
+    let mycology = (categories, shroompedia, index, images);
+    let urls = pages::urls(&mycology)
+        .into_iter()
+        .filter(|url| url != SEARCH_PATH)
+        .collect::<Vec<String>>();
+    let known = urls.iter().cloned().collect::<HashSet<String>>();
+    let out = Path::new(dir);
+
+    let mut assets = BTreeSet::new();
+    let mut written = 0;
+    for url in &urls {
+        let response = generate::get(&mycology, url).await?;
+        if response.status != status::HTTP_200 {
+            eprintln!("warning: {} - {}", url, response.status);
+            continue;
+        }
+        let html = String::from_utf8(response.content)?;
+        let file = out.join(url.trim_matches('/')).join("index.html");
+        let up = "../".repeat(url.split('/').filter(|s| !s.is_empty()).count());
+        write(&file, &relative(&html, &up, &known, &mut assets)).await?;
+        written += 1;
+    }
+    // served for missing urls at any depth, so its links stay root-absolute
+    let html = template::render(templates::NF404, context! {}).await?;
+    write(
+        &out.join(templates::NF404),
+        &relative(&html, "/", &known, &mut assets),
+    )
+    .await?;
+
+    let mut copied = 0;
+    for asset in &assets {
+        if asset.split('/').any(|segment| segment == "..") {
+            eprintln!("warning: {} - not copied", asset);
+            continue;
+        }
+        let to = out.join(asset.trim_start_matches('/'));
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        match fs::copy(format!("{}{}", MYCOLOGY_ROOT, asset), &to).await {
+            Ok(_) => copied += 1,
+            Err(e) => eprintln!("warning: {} - {}", asset, e),
+        }
+    }
+    println!(
+        "{} pages and {} files written to {}",
+        written,
+        copied,
+        out.display()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn write(file: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    Ok(fs::write(file, contents).await?)
+}
+
+// root-relative links prefixed with `up` to the export's root; other pages become
+// their index.html so the export also browses from disk, anything else is an asset
+// to copy
+fn relative(
+    html: &str,
+    up: &str,
+    known: &HashSet<String>,
+    assets: &mut BTreeSet<String>,
+) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some((start, attribute)) = ATTRIBUTES
+        .iter()
+        .filter_map(|attribute| Some((rest.find(attribute)? + attribute.len(), attribute)))
+        .min()
+    {
+        let (before, after) = rest.split_at(start);
+        let (value, after) = after.split_at(after.find('"').unwrap_or(after.len()));
+        out.push_str(before);
+        match *attribute {
+            // `url 320w, url 800w`
+            " srcset=\"" => out.push_str(
+                &value
+                    .split(", ")
+                    .map(|candidate| match candidate.split_once(' ') {
+                        Some((link, width)) => {
+                            format!("{} {}", rewrite(link, up, known, assets), width)
+                        }
+                        None => rewrite(candidate, up, known, assets),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
+            _ => out.push_str(&rewrite(value, up, known, assets)),
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+fn rewrite(link: &str, up: &str, known: &HashSet<String>, assets: &mut BTreeSet<String>) -> String {
+    if !link.starts_with('/') || link.starts_with("//") {
+        return link.to_string();
+    }
+    let (path, suffix) = link.split_at(link.find(['?', '#']).unwrap_or(link.len()));
+    let (decoded, _) = request::split_query(path);
+    match (known.contains(&decoded), path.trim_matches('/')) {
+        (true, "") => format!("{}index.html{}", up, suffix),
+        (true, page) => format!("{}{}/index.html{}", up, page, suffix),
+        (false, asset) => {
+            assets.insert(decoded);
+            format!("{}{}{}", up, asset, suffix)
+        }
+    }
+}
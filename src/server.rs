@@ -1,3 +1,8 @@
-mod request;
+mod autoindex;
+pub mod cache;
+pub mod limit;
+pub mod reload;
+pub mod request;
 pub mod response;
 pub mod run;
+pub mod status;
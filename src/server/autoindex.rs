@@ -0,0 +1,266 @@
+use {
+    crate::{
+        consts::{status, templates, AUTOINDEX_MARKER, PATH},
+        mime,
+        server::{request::GetParam, response::Response},
+        template,
+        types::{Query, Result},
+    },
+    minijinja::context,
+    std::{cmp::Ordering, path::Path, time::SystemTime},
+    tokio::fs,
+};
+
+struct Entry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: SystemTime,
+    mime_type: &'static str,
+}
+
+enum SortBy {
+    Name,
+    Size,
+    Modified,
+    Type,
+}
+
+impl SortBy {
+    fn from_param(param: Option<&str>) -> SortBy {
+        match param {
+            Some("size") => SortBy::Size,
+            Some("modified") => SortBy::Modified,
+            Some("type") => SortBy::Type,
+            _ => SortBy::Name,
+        }
+    }
+
+    fn compare(&self, a: &Entry, b: &Entry) -> Ordering {
+        match self {
+            SortBy::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortBy::Size => a.size.cmp(&b.size),
+            SortBy::Modified => a.modified.cmp(&b.modified),
+            SortBy::Type => a.mime_type.cmp(b.mime_type),
+        }
+    }
+}
+
+pub async fn enabled(dir: &Path) -> bool {
+    fs::metadata(dir.join(AUTOINDEX_MARKER))
+        .await
+        .is_ok_and(|meta| meta.is_file())
+}
+
+// a dotfile or a deny-ruled name in a directory with a marker, or anything below
+// one, which the listing leaves out and so mustn't be served either
+pub async fn denied(path: &Path) -> bool {
+    denied_below(Path::new(PATH.root), path).await
+}
+
+async fn denied_below(root: &Path, path: &Path) -> bool {
+    let mut child = path;
+    while let Some(dir) = child.parent().filter(|dir| dir.starts_with(root)) {
+        if enabled(dir).await {
+            let name = child
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default();
+            let rules = deny_rules(dir).await.unwrap_or_default();
+            if name.starts_with('.') || rules.iter().any(|rule| glob(rule, &name)) {
+                return true;
+            }
+        }
+        child = dir;
+    }
+    false
+}
+
+pub async fn get(dir: &Path, rpath: &str, query: &Query) -> Result<Response> {
+    let deny_rules = deny_rules(dir).await?;
+    let sort_by = SortBy::from_param(query.get_param("sort"));
+    let descending = query.get_param("order") == Some("desc");
+
+    let mut entries = vec![];
+    let mut dir_entries = fs::read_dir(dir).await?;
+    while let Some(entry) = dir_entries.next_entry().await? {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if name.starts_with('.') || deny_rules.iter().any(|rule| glob(rule, &name)) {
+            continue;
+        }
+        let Ok(meta) = entry.metadata().await else {
+            continue;
+        };
+        entries.push(Entry {
+            mime_type: if meta.is_dir() {
+                "inode/directory"
+            } else {
+                mime::from_ext(&name).unwrap_or("application/octet-stream")
+            },
+            is_dir: meta.is_dir(),
+            size: meta.len(),
+            modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            name,
+        });
+    }
+
+    entries.sort_by(|a, b| {
+        b.is_dir.cmp(&a.is_dir).then_with(|| match descending {
+            true => sort_by.compare(b, a),
+            false => sort_by.compare(a, b),
+        })
+    });
+
+    let base = rpath.trim_end_matches('/');
+
+    if query.get_param("format") == Some("json") {
+        Ok(Response {
+            status: status::HTTP_200,
+            mime_type: "application/json",
+            headers: vec![],
+            content: json(base, &entries).into_bytes(),
+        })
+    } else {
+        Ok(Response {
+            status: status::HTTP_200,
+            mime_type: "text/html",
+            headers: vec![],
+            content: htmlify(base, &entries, descending).await?.into_bytes(),
+        })
+    }
+}
+
+async fn htmlify(base: &str, entries: &[Entry], descending: bool) -> Result<String> {
+    let parent = match base.rsplit_once('/') {
+        Some((parent, _)) => format!("{}/", parent),
+        None => "/".to_string(),
+    };
+    template::render(
+        templates::AUTOINDEX,
+        context! {
+            path => format!("{}/", base),
+            parent,
+            order => if descending { "asc" } else { "desc" },
+            entries => entries
+                .iter()
+                .map(|entry| context! {
+                    href => format!(
+                        "{}/{}{}",
+                        base,
+                        entry.name,
+                        if entry.is_dir { "/" } else { "" }
+                    ),
+                    name => entry.name,
+                    dir => entry.is_dir,
+                    size => entry.size,
+                    modified => humantime::format_rfc3339_seconds(entry.modified).to_string(),
+                    mime_type => entry.mime_type,
+                })
+                .collect::<Vec<_>>(),
+        },
+    )
+    .await
+}
+
+fn json(base: &str, entries: &[Entry]) -> String {
+    serde_json::json!({
+        "path": format!("{}/", base),
+        "entries": entries
+            .iter()
+            .map(|entry| serde_json::json!({
+                "name": entry.name,
+                "dir": entry.is_dir,
+                "size": entry.size,
+                "modified": humantime::format_rfc3339_seconds(entry.modified).to_string(),
+                "mime_type": entry.mime_type,
+            }))
+            .collect::<Vec<_>>(),
+    })
+    .to_string()
+}
+
+async fn deny_rules(dir: &Path) -> Result<Vec<String>> {
+    Ok(fs::read_to_string(dir.join(AUTOINDEX_MARKER))
+        .await?
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+fn glob(pattern: &str, name: &str) -> bool {
+    fn glob_bytes(pattern: &[u8], name: &[u8]) -> bool {
+        match (pattern.first(), name.first()) {
+            (None, None) => true,
+            (Some(b'*'), _) => {
+                glob_bytes(&pattern[1..], name)
+                    || (!name.is_empty() && glob_bytes(pattern, &name[1..]))
+            }
+            (Some(p), Some(n)) if p == n || *p == b'?' => glob_bytes(&pattern[1..], &name[1..]),
+            _ => false,
+        }
+    }
+    glob_bytes(pattern.trim_end_matches('/').as_bytes(), name.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::process};
+
+    #[test]
+    fn glob_matches_stars_and_question_marks() {
+        assert!(glob("secret*", "secret.txt"));
+        assert!(glob("*.bak", "notes.bak"));
+        assert!(glob("?.txt", "a.txt"));
+        assert!(glob("private/", "private"));
+        assert!(glob("a*b*c", "aXXbYYc"));
+        assert!(!glob("?.txt", "ab.txt"));
+        assert!(!glob("*.bak", "notes.bak.txt"));
+        assert!(!glob("secret", "secrets"));
+    }
+
+    #[tokio::test]
+    async fn denied_hides_what_the_listing_does() {
+        let root = std::env::temp_dir().join(format!("autoindex-{}", process::id()));
+        let pub_dir = root.join("pub");
+        for dir in ["secret", "open", ".git"] {
+            std::fs::create_dir_all(pub_dir.join(dir)).unwrap();
+        }
+        std::fs::write(pub_dir.join(AUTOINDEX_MARKER), "# private\nsecret*\n").unwrap();
+        for file in [
+            ".hidden",
+            "secret.txt",
+            "a.txt",
+            "secret/inner.txt",
+            "open/b.txt",
+            ".git/config",
+        ] {
+            std::fs::write(pub_dir.join(file), "").unwrap();
+        }
+
+        for (file, expected) in [
+            (".hidden", true),
+            (AUTOINDEX_MARKER, true),
+            ("secret.txt", true),
+            // below a denied directory
+            ("secret/inner.txt", true),
+            (".git/config", true),
+            ("a.txt", false),
+            ("open/b.txt", false),
+        ] {
+            assert_eq!(
+                denied_below(&root, &pub_dir.join(file)).await,
+                expected,
+                "{}",
+                file
+            );
+        }
+        // the root has no marker, so its rules don't apply to pub itself
+        assert!(!denied_below(&root, &pub_dir).await);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
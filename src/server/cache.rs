@@ -0,0 +1,127 @@
+use {
+    crate::{
+        consts::{CACHE_BUDGET, CACHE_MAX_FILE},
+        mime,
+    },
+    std::{
+        collections::{BTreeMap, HashMap},
+        fs::Metadata,
+        io,
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            LazyLock, Mutex,
+        },
+        time::SystemTime,
+    },
+    tokio::fs,
+};
+
+struct Entry {
+    content: Vec<u8>,
+    mime_type: &'static str,
+    modified: SystemTime,
+    size: u64,
+    tick: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<PathBuf, Entry>,
+    order: BTreeMap<u64, PathBuf>,
+    bytes: usize,
+    tick: u64,
+}
+
+pub struct Stats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+static CACHE: LazyLock<Mutex<Lru>> = LazyLock::new(|| Mutex::new(Lru::default()));
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+impl Lru {
+    fn get(&mut self, path: &Path, meta: &Metadata) -> Option<(Vec<u8>, &'static str)> {
+        let modified = meta.modified().ok()?;
+        let entry = self.entries.get(path)?;
+        if entry.modified != modified || entry.size != meta.len() {
+            self.remove(path);
+            return None;
+        }
+        self.tick += 1;
+        let entry = self.entries.get_mut(path)?;
+        self.order.remove(&entry.tick);
+        self.order.insert(self.tick, path.to_path_buf());
+        entry.tick = self.tick;
+        Some((entry.content.clone(), entry.mime_type))
+    }
+
+    fn insert(&mut self, path: &Path, meta: &Metadata, content: &[u8], mime_type: &'static str) {
+        let Ok(modified) = meta.modified() else {
+            return;
+        };
+        self.remove(path);
+        while self.bytes + content.len() > CACHE_BUDGET {
+            match self.order.first_key_value() {
+                Some((_, oldest)) => self.remove(&oldest.clone()),
+                None => return,
+            }
+        }
+        self.tick += 1;
+        self.bytes += content.len();
+        self.order.insert(self.tick, path.to_path_buf());
+        self.entries.insert(
+            path.to_path_buf(),
+            Entry {
+                content: content.to_vec(),
+                mime_type,
+                modified,
+                size: meta.len(),
+                tick: self.tick,
+            },
+        );
+    }
+
+    fn remove(&mut self, path: &Path) {
+        if let Some(entry) = self.entries.remove(path) {
+            self.order.remove(&entry.tick);
+            self.bytes -= entry.content.len();
+        }
+    }
+}
+
+pub async fn read(path: &Path, meta: &Metadata) -> io::Result<(Vec<u8>, &'static str)> {
+    let hit = CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(path, meta);
+    if let Some(hit) = hit {
+        HITS.fetch_add(1, Ordering::Relaxed);
+        return Ok(hit);
+    }
+    MISSES.fetch_add(1, Ordering::Relaxed);
+
+    let content = fs::read(path).await?;
+    let mime_type = mime::from_content(&path.to_string_lossy(), &content);
+    if meta.len() <= CACHE_MAX_FILE {
+        CACHE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path, meta, &content, mime_type);
+    }
+    Ok((content, mime_type))
+}
+
+pub fn stats() -> Stats {
+    let cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    Stats {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+        entries: cache.entries.len(),
+        bytes: cache.bytes,
+    }
+}
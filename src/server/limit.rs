@@ -0,0 +1,40 @@
+use {
+    crate::{consts::rate, types::IpAddr},
+    std::{
+        collections::HashMap,
+        sync::{LazyLock, Mutex},
+        time::Instant,
+    },
+};
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+static BUCKETS: LazyLock<Mutex<HashMap<IpAddr, Bucket>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// takes a token from the client's bucket, false when it's empty
+pub fn allow(ip: IpAddr) -> bool {
+    let now = Instant::now();
+    let burst = rate::BURST as f64;
+    let mut buckets = BUCKETS.lock().unwrap_or_else(|e| e.into_inner());
+    if buckets.len() > rate::CLIENTS {
+        buckets.retain(|_, bucket| now.duration_since(bucket.last) < rate::REFILL * rate::BURST);
+    }
+    let bucket = buckets.entry(ip).or_insert(Bucket {
+        tokens: burst,
+        last: now,
+    });
+    let refilled = now.duration_since(bucket.last).as_secs_f64() / rate::REFILL.as_secs_f64();
+    bucket.tokens = (bucket.tokens + refilled).min(burst);
+    bucket.last = now;
+    match bucket.tokens >= 1.0 {
+        true => {
+            bucket.tokens -= 1.0;
+            true
+        }
+        false => false,
+    }
+}
@@ -0,0 +1,171 @@
+use {
+    crate::{
+        consts::{DATA_DIR, IMAGE_DIR, PATH, VARIANT_DIR, WATCH_DEBOUNCE, WATCH_POLL, YAML_FILE},
+        log,
+        mycology::{images, index::Index, pages, parse},
+        server::status::DataStatus,
+        template,
+        types::Rendered,
+    },
+    notify::{
+        event::ModifyKind, Config, Event, EventKind, PollWatcher, RecommendedWatcher,
+        RecursiveMode, Watcher,
+    },
+    std::{path::Path, sync::Arc, time::SystemTime},
+    tokio::{
+        fs,
+        sync::{mpsc, watch, Mutex},
+        time,
+    },
+};
+
+pub type Site = watch::Sender<Arc<Rendered>>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Change {
+    Data,
+    Templates,
+}
+
+pub async fn data(site: &Site, data_status: &Mutex<DataStatus>, log_file: &fs::File) {
+    match parse::yaml().await {
+        Ok((categories, shroompedia)) => {
+            println!("*** YAML CHANGE DETECTED - RELOADED ***");
+            let index = Index::build(&shroompedia);
+            let (images, errors) = images::build(&shroompedia).await;
+            for (_, e) in errors {
+                eprintln!("{}", e);
+            }
+            let mycology = Arc::new((categories, shroompedia, index, images));
+            let pages = pages::render(mycology.clone()).await;
+            // locked only to swap the results in, so /_status doesn't wait on a reload
+            *data_status.lock().await = DataStatus {
+                loaded: Some(SystemTime::now()),
+                categories: mycology.0.len(),
+                species: mycology.1.len(),
+                pages: pages.len(),
+                error: None,
+            };
+            site.send_replace(Arc::new((mycology, pages)));
+        }
+        Err(e) => {
+            let mut data_status = data_status.lock().await;
+            let repeated = data_status
+                .error
+                .as_ref()
+                .is_some_and(|(_, last)| last.to_string() == e.to_string());
+            if !repeated {
+                if let Ok(log_file) = log_file.try_clone().await {
+                    log::this(log::yaml_error(&e), log_file).await;
+                }
+                data_status.error = Some((SystemTime::now(), e));
+            }
+        }
+    }
+}
+
+// the templates changed but the data didn't, so only the pages are rendered again
+async fn rerender(site: &Site, data_status: &Mutex<DataStatus>) {
+    let mycology = site.borrow().0.clone();
+    let pages = pages::render(mycology.clone()).await;
+    data_status.lock().await.pages = pages.len();
+    site.send_replace(Arc::new((mycology, pages)));
+}
+
+pub async fn watch(site: Site, data_status: Arc<Mutex<DataStatus>>, log_file: fs::File) {
+    let (changes_tx, mut changes) = mpsc::unbounded_channel();
+    // dropping the watcher stops the events, so it lives as long as this task
+    let _watcher = match watcher(changes_tx) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            eprintln!("{} - cannot watch for changes, reloading is disabled", e);
+            None
+        }
+    };
+
+    while let Some(change) = changes.recv().await {
+        let mut pending = vec![change];
+        while let Ok(Some(change)) = time::timeout(WATCH_DEBOUNCE, changes.recv()).await {
+            if !pending.contains(&change) {
+                pending.push(change);
+            }
+        }
+        if pending.contains(&Change::Templates) {
+            match template::reload().await {
+                Ok(_) => {
+                    println!("*** TEMPLATE CHANGE DETECTED - RELOADED ***");
+                    if !pending.contains(&Change::Data) {
+                        rerender(&site, &data_status).await;
+                    }
+                }
+                Err(e) => eprintln!("{} - template reload failed, keeping the old ones", e),
+            }
+        }
+        if pending.contains(&Change::Data) {
+            data(&site, &data_status, &log_file).await;
+        }
+    }
+}
+
+fn classify(path: &Path) -> Option<Change> {
+    if path == Path::new(YAML_FILE)
+        || path.starts_with(DATA_DIR)
+        || path.starts_with(IMAGE_DIR)
+        || path.starts_with(VARIANT_DIR)
+    {
+        Some(Change::Data)
+    } else if path.starts_with(PATH.templates) {
+        Some(Change::Templates)
+    } else {
+        None
+    }
+}
+
+fn watcher(changes: mpsc::UnboundedSender<Change>) -> notify::Result<Box<dyn Watcher + Send>> {
+    let handler = move |event: notify::Result<Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        // our own reads show up as access events
+        if matches!(
+            event.kind,
+            EventKind::Access(_) | EventKind::Modify(ModifyKind::Metadata(_))
+        ) {
+            return;
+        }
+        for change in event.paths.iter().filter_map(|path| classify(path)) {
+            let _ = changes.send(change);
+        }
+    };
+
+    match RecommendedWatcher::new(handler.clone(), Config::default()).and_then(add_watches) {
+        Ok(v) => Ok(Box::new(v)),
+        Err(e) => {
+            eprintln!("{} - falling back to polling every {:?}", e, WATCH_POLL);
+            Ok(Box::new(add_watches(PollWatcher::new(
+                handler,
+                Config::default().with_poll_interval(WATCH_POLL),
+            )?)?))
+        }
+    }
+}
+
+fn add_watches<W: Watcher>(mut watcher: W) -> notify::Result<W> {
+    // the yaml's directory rather than the file, so rename-on-save is seen
+    let yaml_dir = Path::new(YAML_FILE).parent().unwrap_or(Path::new("/"));
+    for (path, mode) in [
+        (yaml_dir, RecursiveMode::NonRecursive),
+        (Path::new(DATA_DIR), RecursiveMode::Recursive),
+        (Path::new(PATH.templates), RecursiveMode::Recursive),
+        (Path::new(IMAGE_DIR), RecursiveMode::Recursive),
+        (Path::new(VARIANT_DIR), RecursiveMode::Recursive),
+    ] {
+        match watcher.watch(path, mode) {
+            Err(e) if matches!(e.kind, notify::ErrorKind::PathNotFound) => {
+                eprintln!("{} {} - not watched", e, path.display())
+            }
+            result => result?,
+        }
+    }
+    Ok(watcher)
+}
@@ -2,7 +2,7 @@ use {
   crate::{
     consts::{domains, FIELDS},
     server::response::Host,
-    types::{Buffer, IpAddr, Request},
+    types::{Buffer, IpAddr, Query, Request},
   },
   std::{
     io::{self},
@@ -17,6 +17,7 @@ pub struct RequestInfo {
   pub user_agent: Option<String>,
   pub ip: Option<IpAddr>,
   pub referer: Option<String>,
+  pub accepts_gzip: bool,
 }
 
 pub async fn parse_tcp_stream(buf: Buffer<'_>) -> Result<RequestInfo, io::Error> {
@@ -38,6 +39,9 @@ pub async fn parse_tcp_stream(buf: Buffer<'_>) -> Result<RequestInfo, io::Error>
     user_agent: request.get_field(FIELDS.user_agent),
     ip: request.get_ip(),
     referer: request.get_field(FIELDS.referer),
+    accepts_gzip: request
+      .get_field(FIELDS.accept_encoding)
+      .is_some_and(|v| accepts(&v, "gzip")),
   })
 }
 
@@ -93,3 +97,84 @@ impl ArrFromIp for Option<&String> {
     })
   }
 }
+
+pub fn split_query(path: &str) -> (String, Query) {
+  let (path, query) = path.split_once('?').unwrap_or((path, ""));
+  (
+    percent_decode(path),
+    query
+      .split('&')
+      .filter(|p| !p.is_empty())
+      .map(|p| {
+        let (k, v) = p.split_once('=').unwrap_or((p, ""));
+        (
+          percent_decode(&k.replace('+', " ")),
+          percent_decode(&v.replace('+', " ")),
+        )
+      })
+      .collect(),
+  )
+}
+
+pub trait GetParam {
+  fn get_param(&self, key: &str) -> Option<&str>;
+}
+
+impl GetParam for Query {
+  fn get_param(&self, key: &str) -> Option<&str> {
+    self
+      .iter()
+      .find(|(k, _)| k == key)
+      .map(|(_, v)| v.as_str())
+  }
+}
+
+// listed in an Accept-Encoding header without `q=0`; a q-value that doesn't parse
+// is ignored
+fn accepts(header: &str, coding: &str) -> bool {
+  header.split(',').any(|entry| {
+    let mut params = entry.split(';').map(str::trim);
+    params.next().is_some_and(|name| name.eq_ignore_ascii_case(coding))
+      && params
+        .find_map(|param| param.strip_prefix("q="))
+        .and_then(|q| q.parse::<f32>().ok())
+        .is_none_or(|q| q > 0.0)
+  })
+}
+
+fn percent_decode(s: &str) -> String {
+  let bytes = s.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    let hex = bytes
+      .get(i + 1..i + 3)
+      .and_then(|h| std::str::from_utf8(h).ok())
+      .and_then(|h| u8::from_str_radix(h, 16).ok());
+    match (bytes[i], hex) {
+      (b'%', Some(b)) => {
+        decoded.push(b);
+        i += 3;
+      }
+      (b, _) => {
+        decoded.push(b);
+        i += 1;
+      }
+    }
+  }
+  String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn accepts_only_nonzero_q() {
+    assert!(accepts("gzip, deflate, br", "gzip"));
+    assert!(accepts("br;q=1.0, GZIP;q=0.5", "gzip"));
+    assert!(!accepts("gzip;q=0", "gzip"));
+    assert!(!accepts("deflate, gzip; q=0.000", "gzip"));
+    assert!(!accepts("x-gzip, br", "gzip"));
+  }
+}
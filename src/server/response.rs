@@ -1,10 +1,13 @@
 use {
     crate::{
-        consts::{status, MIMETYPES, PATH},
-        html,
+        consts::{status, templates, PATH},
+        server::{autoindex, cache, request},
+        template,
         types::Result,
     },
-    std::fs,
+    minijinja::context,
+    std::io,
+    tokio::fs,
 };
 
 pub enum Host {
@@ -15,55 +18,77 @@ pub enum Host {
 pub struct Response {
     pub status: &'static str,
     pub mime_type: &'static str,
+    pub headers: Vec<(&'static str, String)>,
     pub content: Vec<u8>,
 }
 
-pub fn get(rpath: &String) -> Result<Response> {
-    let path = format!("{}{}", PATH.root, &rpath);
-    let is_dir = fs::metadata(&path)?.is_dir();
-    let wanted_file = format!("{}{}", path, if is_dir { "/index.html" } else { "" });
-    let file_type = wanted_file.split('.').last().unwrap_or("");
-    let mime_type = MIMETYPES
-        .into_iter()
-        .fold("text/plain", |a, (b, c)| if b == file_type { c } else { a });
+pub async fn get(rpath: &str) -> Result<Response> {
+    let (rpath, query) = request::split_query(rpath);
+    if rpath.split('/').any(|segment| segment == "..") {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Permission denied",
+        )));
+    }
+    let path = fs::canonicalize(format!("{}{}", PATH.root, &rpath)).await?;
+    if autoindex::denied(&path).await {
+        return Err(Box::new(io::Error::from(io::ErrorKind::NotFound)));
+    }
+    let meta = fs::metadata(&path).await?;
+    let (wanted_file, meta) = if meta.is_dir() {
+        let index = path.join("index.html");
+        let index_meta = fs::metadata(&index).await;
+        if index_meta
+            .as_ref()
+            .is_err_and(|e| e.kind() == io::ErrorKind::NotFound)
+            && autoindex::enabled(&path).await
+        {
+            return autoindex::get(&path, &rpath, &query).await;
+        }
+        (index, index_meta?)
+    } else {
+        (path, meta)
+    };
+    let (content, mime_type) = cache::read(&wanted_file, &meta).await?;
     Ok(Response {
         status: status::HTTP_200,
         mime_type,
-        content: fs::read(wanted_file)?,
+        headers: vec![],
+        content,
     })
 }
 
-pub trait CheckErr {
-    fn replace_err(self) -> Result<Response>;
-}
-
-impl CheckErr for Result<Response> {
-    fn replace_err(self) -> Result<Response> {
-        match self {
+pub mod err {
+    use super::*;
+    pub async fn replace(response: Result<Response>) -> Result<Response> {
+        match response {
             Ok(v) => Ok(v),
             Err(e) => match e.to_string().contains("Permission denied") {
-                true => err::pd403(),
-                false => err::nf404(),
+                true => pd403().await,
+                false => nf404().await,
             },
         }
     }
-}
 
-pub mod err {
-    use super::*;
-    pub fn nf404() -> Result<Response> {
+    pub async fn nf404() -> Result<Response> {
         Ok(Response {
             status: status::HTTP_404,
             mime_type: "text/html",
-            content: html::from_file(PATH.nf404)?.into_bytes(),
+            headers: vec![],
+            content: template::render(templates::NF404, context! {})
+                .await?
+                .into_bytes(),
         })
     }
 
-    pub fn pd403() -> Result<Response> {
+    pub async fn pd403() -> Result<Response> {
         Ok(Response {
             status: status::HTTP_403,
             mime_type: "text/html",
-            content: html::from_file(PATH.pd403)?.into_bytes(),
+            headers: vec![],
+            content: template::render(templates::PD403, context! {})
+                .await?
+                .into_bytes(),
         })
     }
 }
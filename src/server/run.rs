@@ -2,22 +2,23 @@ use {
     crate::{
         consts,
         log::{self, Log},
-        mycology::{
-            self,
-            parse::{self, Shroompedia},
-        },
+        mime,
+        mycology::{self, index::Index, pages},
         server::{
+            cache,
             request::*,
+            reload,
             response::{self, *},
+            status::{self, DataStatus},
         },
-        types::{Categories, Content, IpAddr, Result},
+        types::{Content, IpAddr, Rendered, Result},
     },
-    std::{sync::Arc, time::SystemTime},
+    std::{collections::HashMap, sync::Arc, time::SystemTime},
     tokio::{
         fs,
         io::{AsyncWriteExt, BufReader},
         net::{TcpListener, TcpStream},
-        sync::Mutex,
+        sync::{watch, Mutex},
     },
 };
 
@@ -28,15 +29,24 @@ struct CxnInfo {
 }
 
 pub async fn start_server() -> Result<()> {
-    let mut last_modified = SystemTime::UNIX_EPOCH;
-    let mut yaml: Arc<(Categories, Shroompedia)> = Arc::new((vec![], vec![])); // empty vec to initialise
+    let empty = Arc::new((vec![], vec![], Index::default(), HashMap::new()));
+    let (site, yaml) = watch::channel(Arc::new((empty, HashMap::new()))); // empty to initialise
     let cxn_info = Arc::new(Mutex::new(CxnInfo {
         ip: [0, 0, 0, 0],
         unique_cxn: 1,
         total_cxn: 1,
     }));
+    let data_status = Arc::new(Mutex::new(DataStatus::default()));
 
     let log_file = log::open().await;
+    mime::load().await;
+
+    reload::data(&site, &data_status, &log_file).await;
+    tokio::spawn(reload::watch(
+        site,
+        data_status.clone(),
+        log_file.try_clone().await?,
+    ));
 
     let uptime = SystemTime::now();
 
@@ -45,13 +55,17 @@ pub async fn start_server() -> Result<()> {
     loop {
         let (stream, _) = listener.accept().await?;
 
-        (yaml, last_modified) = memo_yaml(last_modified, yaml).await?;
-
-        let (log_file, yaml, cxn_info) =
-            (log_file.try_clone().await?, yaml.clone(), cxn_info.clone());
+        let (log_file, yaml, cxn_info, data_status) = (
+            log_file.try_clone().await?,
+            yaml.borrow().clone(),
+            cxn_info.clone(),
+            data_status.clone(),
+        );
 
         tokio::spawn(async move {
-            if let Err(e) = handle_connection(stream, uptime, &yaml, log_file, cxn_info).await {
+            if let Err(e) =
+                handle_connection(stream, uptime, &yaml, log_file, cxn_info, data_status).await
+            {
                 eprintln!("{}", e)
             }
         });
@@ -61,9 +75,10 @@ pub async fn start_server() -> Result<()> {
 async fn handle_connection(
     mut stream: TcpStream,
     uptime: SystemTime,
-    yaml: &(Categories,Shroompedia),
+    (yaml, pages): &Rendered,
     log_file: fs::File,
     cxn_info: Arc<Mutex<CxnInfo>>,
+    data_status: Arc<Mutex<DataStatus>>,
 ) -> Result<()> {
     let cxn_time = SystemTime::now();
 
@@ -73,6 +88,7 @@ async fn handle_connection(
         user_agent,
         ip,
         referer,
+        accepts_gzip,
     } = parse_tcp_stream(BufReader::new(&mut stream)).await?;
 
     let mut cxn_info = cxn_info.lock().await;
@@ -85,14 +101,24 @@ async fn handle_connection(
     cxn_info.ip = ip.unwrap_or_default();
     drop(cxn_info);
 
-    let response = if let (Some(domain), Some(path)) = (&host, &path) {
-        match domain {
-            Host::Mycology => mycology::generate::get(yaml, path).await,
-            Host::Site => response::get(path),
-        }
-        .replace_err()
+    // only on the known hosts, not whatever Host header a scanner sends
+    let response = if host.is_some() && path.as_deref() == Some(consts::STATUS_PATH) {
+        Ok(status::get(&*data_status.lock().await, uptime))
+    } else if let (Some(domain), Some(path)) = (&host, &path) {
+        err::replace(match domain {
+            Host::Mycology if mycology::img::handles(path) => {
+                mycology::img::get(path, ip.unwrap_or_default()).await
+            }
+            Host::Mycology if mycology::api::handles(path) => mycology::api::get(yaml, path).await,
+            Host::Mycology => match pages::lookup(pages, path) {
+                Some(page) => Ok(page.response(accepts_gzip)),
+                None => mycology::generate::get(yaml, path).await,
+            },
+            Host::Site => response::get(path).await,
+        })
+        .await
     } else {
-        err::nf404()
+        err::nf404().await
     }?;
 
     let status = response
@@ -120,6 +146,7 @@ async fn handle_connection(
             start_time: uptime,
             unique_cxn,
             total_cxn,
+            cache: cache::stats(),
         }
         .stringify(ip.unwrap_or_default(), last_ip),
         log_file,
@@ -128,19 +155,6 @@ async fn handle_connection(
     Ok(())
 }
 
-async fn memo_yaml(
-    last_modified: SystemTime,
-    memo: Arc<(Categories, Shroompedia)>,
-) -> Result<(Arc<(Categories, Shroompedia)>, SystemTime)> {
-    let curr_modified = fs::metadata(consts::YAML_FILE).await?.modified()?;
-    if last_modified == curr_modified {
-        Ok((memo, curr_modified))
-    } else {
-        println!("*** YAML CHANGE DETECTED - RELOADED ***");
-        Ok((Arc::new(parse::yaml().await), curr_modified))
-    }
-}
-
 trait Prepend {
     fn prepend_headers(self) -> Content;
 }
@@ -149,10 +163,14 @@ impl Prepend for Response {
     fn prepend_headers(self) -> Content {
         [
             format!(
-                "{}\r\nContent-Length: {}\r\nContent-Type: {}\r\n\r\n",
+                "{}\r\nContent-Length: {}\r\nContent-Type: {}\r\n{}\r\n",
                 self.status,
                 self.content.len(),
-                self.mime_type
+                self.mime_type,
+                self.headers
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}\r\n", name, value))
+                    .collect::<String>()
             )
             .into_bytes(),
             self.content,
@@ -0,0 +1,72 @@
+use {
+    crate::{
+        consts::{status, YAML_FILE},
+        mycology::parse::YamlError,
+        server::{cache, response::Response},
+    },
+    serde_json::{json, Value},
+    std::{path::Path, time::SystemTime},
+};
+
+#[derive(Default)]
+pub struct DataStatus {
+    pub loaded: Option<SystemTime>,
+    pub categories: usize,
+    pub species: usize,
+    pub pages: usize,
+    pub error: Option<(SystemTime, YamlError)>,
+}
+
+fn timestamp(time: SystemTime) -> String {
+    humantime::format_rfc3339_seconds(time).to_string()
+}
+
+// data files named relative to the data directory, the page doesn't say where that
+// is on disk
+fn relative(text: &str) -> String {
+    match Path::new(YAML_FILE).parent() {
+        Some(data) => text.replace(&format!("{}/", data.display()), ""),
+        None => text.to_string(),
+    }
+}
+
+pub fn get(data_status: &DataStatus, uptime: SystemTime) -> Response {
+    let cache = cache::stats();
+    let error = match &data_status.error {
+        Some((since, e)) => json!({
+            "since": timestamp(*since),
+            "file": relative(&e.file),
+            "line": e.line,
+            "column": e.column,
+            "message": relative(&e.message),
+        }),
+        None => Value::Null,
+    };
+    Response {
+        status: match data_status.error {
+            Some(_) => status::HTTP_503,
+            None => status::HTTP_200,
+        },
+        mime_type: "application/json",
+        headers: vec![],
+        content: json!({
+            "status": if data_status.error.is_some() { "error" } else { "ok" },
+            "uptime_secs": uptime.elapsed().map(|d| d.as_secs()).unwrap_or_default(),
+            "data": {
+                "loaded": data_status.loaded.map(timestamp),
+                "categories": data_status.categories,
+                "species": data_status.species,
+                "pages": data_status.pages,
+            },
+            "reload_error": error,
+            "cache": {
+                "hits": cache.hits,
+                "misses": cache.misses,
+                "entries": cache.entries,
+                "bytes": cache.bytes,
+            },
+        })
+        .to_string()
+        .into_bytes(),
+    }
+}
@@ -0,0 +1,154 @@
+use {
+    crate::{
+        consts::IMAGE_URL,
+        escape,
+        types::Result,
+    },
+    minijinja::{escape_formatter, AutoEscape, Environment, Error, Value},
+    std::{
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, LazyLock, RwLock,
+        },
+    },
+    tokio::fs,
+};
+
+// the repo's own templates under test, so they needn't be deployed first
+#[cfg(not(test))]
+const ROOT: &str = crate::consts::PATH.templates;
+#[cfg(test)]
+const ROOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/templates");
+
+// set by render-all, so templates can leave out what only works against the server
+static STATIC_EXPORT: AtomicBool = AtomicBool::new(false);
+
+static TEMPLATES: LazyLock<RwLock<Option<Arc<Environment<'static>>>>> =
+    LazyLock::new(|| RwLock::new(None));
+
+pub async fn render(name: &str, ctx: Value) -> Result<String> {
+    Ok(environment().await?.get_template(name)?.render(ctx)?)
+}
+
+pub fn static_export() {
+    STATIC_EXPORT.store(true, Ordering::Relaxed);
+    *TEMPLATES.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+async fn environment() -> Result<Arc<Environment<'static>>> {
+    let loaded = TEMPLATES.read().unwrap_or_else(|e| e.into_inner()).clone();
+    match loaded {
+        Some(env) => Ok(env),
+        None => reload().await,
+    }
+}
+
+pub async fn reload() -> Result<Arc<Environment<'static>>> {
+    let mut env = Environment::new();
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.add_global("image_url", IMAGE_URL);
+    env.add_global("static_export", STATIC_EXPORT.load(Ordering::Relaxed));
+    env.set_formatter(
+        |out, state, value| match (state.auto_escape(), value.as_str()) {
+            (AutoEscape::Html, Some(s)) if !value.is_safe() => {
+                out.write_str(&escape::html(s)).map_err(Error::from)
+            }
+            _ => escape_formatter(out, state, value),
+        },
+    );
+    env.add_filter("url", |s: &str| Value::from_safe_string(escape::url(s)));
+    env.add_filter("filesize", filesize);
+    for (name, path) in walk().await? {
+        env.add_template_owned(name, fs::read_to_string(path).await?)?;
+    }
+    let env = Arc::new(env);
+    *TEMPLATES.write().unwrap_or_else(|e| e.into_inner()) = Some(env.clone());
+    Ok(env)
+}
+
+// every file under the template dir, named by its path relative to it
+async fn walk() -> Result<Vec<(String, PathBuf)>> {
+    let root = PathBuf::from(ROOT);
+    let mut files = vec![];
+    let mut dirs = vec![root.clone()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let meta = entry.metadata().await?;
+            let path = entry.path();
+            if meta.is_dir() {
+                dirs.push(path);
+            } else if let Ok(name) = path.strip_prefix(&root) {
+                files.push((name.to_string_lossy().into_owned(), path));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn filesize(bytes: u64) -> String {
+    ["B", "KiB", "MiB", "GiB"]
+        .into_iter()
+        .enumerate()
+        .find_map(|(i, unit)| {
+            let scaled = bytes as f64 / 1024f64.powi(i as i32);
+            (scaled < 1024.0 || unit == "GiB").then(|| match i {
+                0 => format!("{} {}", bytes, unit),
+                _ => format!("{:.1} {}", scaled, unit),
+            })
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::consts::templates, minijinja::context};
+
+    const NASTY: &str = r#"</script><img src=x onerror="alert(1)">"#;
+
+    fn assert_escaped(html: &str) {
+        assert!(!html.contains("<img src=x"), "{}", html);
+        assert!(!html.contains(r#"onerror="alert"#), "{}", html);
+        assert!(html.contains("&lt;/script&gt;&lt;img src=x onerror=&quot;alert(1)&quot;&gt;"));
+    }
+
+    #[tokio::test]
+    async fn species_escapes_text() {
+        let html = render(
+            templates::SPECIES,
+            context! {
+                latin_name => "Boletus edulis",
+                common_name => NASTY,
+                blurb => NASTY,
+                images => vec![context! { path => "fungi/a.jpg", alt => NASTY, caption => NASTY }],
+            },
+        )
+        .await
+        .unwrap();
+        assert_escaped(&html);
+        assert!(html.contains(r#"alt="&lt;/script&gt;"#));
+    }
+
+    #[tokio::test]
+    async fn menu_escapes_search_names() {
+        let html = render(
+            templates::MENU,
+            context! {
+                categories => vec![context! { name => "x\"y", title => NASTY }],
+                search => vec![context! { name => NASTY, url => "/fungi" }],
+                q => NASTY,
+            },
+        )
+        .await
+        .unwrap();
+        assert_escaped(&html);
+        assert!(html.contains(r#"href="/x%22y""#));
+        // the suggestions script is the only one, and nothing closed it early
+        let script = &html[html.find("<script>").unwrap()..];
+        assert_eq!(script.matches("</script>").count(), 1);
+        assert!(!script.contains("&lt;/script&gt;"));
+    }
+}
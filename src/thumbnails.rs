@@ -0,0 +1,110 @@
+use {
+    crate::{
+        consts::{IMAGE_DIR, VARIANT_DIR},
+        mycology::{
+            images::{self, Encoding},
+            parse,
+        },
+        types::Result,
+    },
+    image::DynamicImage,
+    std::{
+        path::{Path, PathBuf},
+        process::ExitCode,
+    },
+    tokio::{fs, task},
+};
+
+// every width and encoding of every species image that's missing or older than its
+// original, written under VARIANT_DIR; a running server picks them up as they land.
+// Images that can't be decoded (avif) are shown as they are
+pub async fn run() -> Result<ExitCode> {
+    let (_, shroompedia) = match parse::yaml().await {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return Ok(ExitCode::from(2));
+        }
+    };
+    let (images, errors) = images::build(&shroompedia).await;
+    for (_, e) in errors {
+        eprintln!("warning: {}", e);
+    }
+
+    let (mut written, mut current, mut failed) = (0, 0, 0);
+    for image in images.values().flatten() {
+        if !images::decodable(&image.path) {
+            continue;
+        }
+        match make(&image.path).await {
+            Ok((new, old)) => {
+                written += new;
+                current += old;
+            }
+            Err(e) => {
+                eprintln!("error: {} - {}", image.path, e.to_string().trim_end());
+                failed += 1;
+            }
+        }
+    }
+    println!(
+        "{} written - {} up to date - {} images failed",
+        written, current, failed
+    );
+    Ok(match failed {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::from(1),
+    })
+}
+
+// (written, already up to date) for one image
+async fn make(path: &str) -> Result<(usize, usize)> {
+    let original = Path::new(IMAGE_DIR).join(path);
+    let modified = fs::metadata(&original).await?.modified()?;
+    let (width, _) = image::image_dimensions(&original)?;
+
+    let mut todo = vec![];
+    let mut current = 0;
+    for width in images::widths(width) {
+        for encoding in Encoding::VARIANTS {
+            let to = Path::new(VARIANT_DIR).join(images::variant(path, width, *encoding));
+            let fresh = fs::metadata(&to)
+                .await
+                .and_then(|meta| meta.modified())
+                .is_ok_and(|made| made >= modified);
+            match fresh {
+                true => current += 1,
+                false => todo.push((width, *encoding, to)),
+            }
+        }
+    }
+    if todo.is_empty() {
+        return Ok((0, current));
+    }
+
+    // decoding and encoding are all cpu, so off the runtime
+    let encoded = task::spawn_blocking(move || -> Result<Vec<(PathBuf, Vec<u8>)>> {
+        let decoded = image::open(&original)?;
+        let mut resized: Option<(u32, DynamicImage)> = None;
+        let mut encoded = vec![];
+        for (width, encoding, to) in todo {
+            if resized.as_ref().is_none_or(|(w, _)| *w != width) {
+                resized = Some((width, images::resize(&decoded, width)));
+            }
+            if let Some((_, image)) = &resized {
+                encoded.push((to, encoding.encode(image)?));
+            }
+        }
+        Ok(encoded)
+    })
+    .await??;
+
+    let written = encoded.len();
+    for (to, bytes) in encoded {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&to, bytes).await?;
+    }
+    Ok((written, current))
+}
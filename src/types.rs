@@ -1,26 +1,26 @@
 use {
-    crate::mycology::generate::{CatInfo, SpeInfo},
-    std::{error, result},
+    crate::mycology::{
+        generate::CatInfo, images::Image, index::Index, pages::Page, parse::Shroompedia,
+    },
+    std::{collections::HashMap, error, result, sync::Arc},
     tokio::{io::BufReader, net::TcpStream},
 };
 
-pub type GenFold<'g> = Box<dyn FnMut(String, &SpeInfo) -> String + 'g>;
-
-pub type SpecFold<'s> = Box<dyn FnMut(String, usize) -> String + 's>;
-
 pub type Condition = Box<dyn Fn(&(usize, &String)) -> bool>;
 
 pub type Buffer<'b> = BufReader<&'b mut TcpStream>;
 
 pub type Categories = Vec<CatInfo>;
 
-pub type YamlString = String;
+// url -> its images
+pub type Images = HashMap<String, Vec<Image>>;
 
-pub type YamlChunks = Vec<Vec<YamlLine>>;
+pub type Mycology = (Categories, Shroompedia, Index, Images);
 
-pub type YamlLines = Vec<YamlLine>;
+pub type Pages = HashMap<String, Page>;
 
-pub type YamlLine = String;
+// the data and the pages rendered from it, swapped together on a reload
+pub type Rendered = (Arc<Mycology>, Pages);
 
 pub type CxnLog<'l> = &'l mut String;
 
@@ -28,6 +28,11 @@ pub type IpAddr = [u8; 4];
 
 pub type Content = Vec<u8>;
 
+// every (offset, bytes) of a file signature has to match
+pub type Signature = &'static [(usize, &'static [u8])];
+
 pub type Request = Vec<String>;
 
-pub type Result<T> = result::Result<T, Box<dyn error::Error>>;
+pub type Query = Vec<(String, String)>;
+
+pub type Result<T> = result::Result<T, Box<dyn error::Error + Send + Sync>>;